@@ -0,0 +1,9 @@
+//! English model for [instant-segment](https://docs.rs/instant-segment)
+//!
+//! This crate only holds the data for `Segmenter::english()`, so that the model is not
+//! downloaded by users of `instant-segment` that don't enable its `english` feature.
+
+/// Gzip compressed model in the compact encoding, built from `data/en-unigrams.txt`
+///
+/// The model has no bigrams.
+pub const MODEL: &[u8] = include_bytes!("../en.model.gz");
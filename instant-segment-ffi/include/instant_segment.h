@@ -0,0 +1,156 @@
+/* Generated by cbindgen from instant-segment-ffi, do not edit */
+
+#ifndef INSTANT_SEGMENT_H
+#define INSTANT_SEGMENT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of an API call
+typedef enum InstantSegmentStatus {
+  // The call succeeded
+  INSTANT_SEGMENT_STATUS_OK = 0,
+  // A required pointer was null, or a NUL-terminated string was not valid UTF-8
+  INSTANT_SEGMENT_STATUS_INVALID_ARGUMENT = 1,
+  // The input text contains characters other than lowercase ASCII letters and digits, or is
+  // not valid UTF-8
+  INSTANT_SEGMENT_STATUS_INVALID_CHARACTER = 2,
+  // The buffer passed in is too small for the result
+  INSTANT_SEGMENT_STATUS_BUFFER_TOO_SMALL = 3,
+  // The word is not in the vocabulary
+  INSTANT_SEGMENT_STATUS_NOT_FOUND = 4,
+  // A file could not be read
+  INSTANT_SEGMENT_STATUS_IO = 5,
+  // The model data is malformed or was written in an unsupported format version
+  INSTANT_SEGMENT_STATUS_MODEL = 6,
+  // An internal error occurred
+  INSTANT_SEGMENT_STATUS_PANIC = 7,
+} InstantSegmentStatus;
+
+// Buffers used while segmenting, which can be reused between calls
+typedef struct InstantSegmentSearch InstantSegmentSearch;
+
+// A segmenter holding the word scores
+typedef struct InstantSegmentSegmenter InstantSegmentSegmenter;
+
+// The byte range of a word in the input
+typedef struct InstantSegmentSpan {
+  // Offset of the first byte of the word
+  size_t start;
+  // Offset just past the last byte of the word
+  size_t end;
+} InstantSegmentSpan;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Load a segmenter from the model file at `path`
+//
+// Accepts the files written by `Segmenter::save()` and `Segmenter::save_compact()`, optionally
+// gzip compressed. On success, the segmenter is written to `out` and must be released with
+// `instant_segment_segmenter_free()`.
+//
+// # Safety
+//
+// `path` must be a NUL-terminated string and `out` must be valid for writes.
+enum InstantSegmentStatus instant_segment_segmenter_load(const char *path,
+                                                         struct InstantSegmentSegmenter **out);
+
+// Load a segmenter from the `len` bytes of model data at `data`
+//
+// Like `instant_segment_segmenter_load()`, but for model data already in memory.
+//
+// # Safety
+//
+// `data` must be valid for reads of `len` bytes and `out` must be valid for writes.
+enum InstantSegmentStatus instant_segment_segmenter_load_bytes(const uint8_t *data,
+                                                               size_t len,
+                                                               struct InstantSegmentSegmenter **out);
+
+// Create a segmenter from unigram and bigram count files
+//
+// The files contain one word or pair of words per line, followed by a tab and the count, like
+// the files in the `data` directory of the repository.
+//
+// # Safety
+//
+// `unigrams` and `bigrams` must be NUL-terminated strings and `out` must be valid for writes.
+enum InstantSegmentStatus instant_segment_segmenter_from_files(const char *unigrams,
+                                                               const char *bigrams,
+                                                               struct InstantSegmentSegmenter **out);
+
+// Release a segmenter
+//
+// # Safety
+//
+// `segmenter` must be null or a segmenter that has not been released yet.
+void instant_segment_segmenter_free(struct InstantSegmentSegmenter *segmenter);
+
+// Allocate a search, which must be released with `instant_segment_search_free()`
+struct InstantSegmentSearch *instant_segment_search_new(void);
+
+// Release a search
+//
+// # Safety
+//
+// `search` must be null or a search that has not been released yet.
+void instant_segment_search_free(struct InstantSegmentSearch *search);
+
+// Segment the `input_len` bytes of text at `input` into words
+//
+// The spans of the words are written to `spans`, which has room for `capacity` spans, and the
+// number of words to `len`. If there are more words than fit, `len` is set to the number of
+// words and `INSTANT_SEGMENT_STATUS_BUFFER_TOO_SMALL` is returned, so the call can be repeated
+// with a larger buffer. If `score` is not null, the score of the segmentation is written to it.
+// Input that is not valid UTF-8 results in `INSTANT_SEGMENT_STATUS_INVALID_CHARACTER`.
+//
+// # Safety
+//
+// `segmenter` and `search` must be valid, `input` must be valid for reads of `input_len` bytes,
+// `spans` must be valid for writes of `capacity` spans, and `len` must be valid for writes.
+enum InstantSegmentStatus instant_segment_segment(const struct InstantSegmentSegmenter *segmenter,
+                                                  struct InstantSegmentSearch *search,
+                                                  const char *input,
+                                                  size_t input_len,
+                                                  struct InstantSegmentSpan *spans,
+                                                  size_t capacity,
+                                                  size_t *len,
+                                                  double *score);
+
+// Score the sentence made up of the `len` NUL-terminated `words`
+//
+// Returns `INSTANT_SEGMENT_STATUS_INVALID_ARGUMENT` if there are no words.
+//
+// # Safety
+//
+// `segmenter` must be valid, `words` must be valid for reads of `len` NUL-terminated strings,
+// and `score` must be valid for writes.
+enum InstantSegmentStatus instant_segment_score_sentence(const struct InstantSegmentSegmenter *segmenter,
+                                                         const char *const *words,
+                                                         size_t len,
+                                                         double *score);
+
+// Look up the base-10 logarithm of the relative frequency of the NUL-terminated `word`
+//
+// Returns `INSTANT_SEGMENT_STATUS_NOT_FOUND` if the word is not in the vocabulary.
+//
+// # Safety
+//
+// `segmenter` must be valid, `word` must be a NUL-terminated string, and `score` must be valid
+// for writes.
+enum InstantSegmentStatus instant_segment_unigram_log_prob(const struct InstantSegmentSegmenter *segmenter,
+                                                           const char *word,
+                                                           double *score);
+
+// A NUL-terminated description of `status`, which must not be released
+const char *instant_segment_status_message(enum InstantSegmentStatus status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* INSTANT_SEGMENT_H */
@@ -0,0 +1,422 @@
+//! C API for Instant Segment
+//!
+//! The API is declared in `include/instant_segment.h`, which is generated by cbindgen. Functions
+//! that can fail return an [`InstantSegmentStatus`] and write their results through out
+//! pointers. Strings are UTF-8; input text is passed as a pointer and a length, other strings
+//! are NUL-terminated.
+//!
+//! A segmenter can be shared between threads, but each thread needs its own search.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice, str};
+
+use instant_segment::{ModelError, Search, Segmenter};
+
+/// Result of an API call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InstantSegmentStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was null, or a NUL-terminated string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The input text contains characters other than lowercase ASCII letters and digits, or is
+    /// not valid UTF-8
+    InvalidCharacter = 2,
+    /// The buffer passed in is too small for the result
+    BufferTooSmall = 3,
+    /// The word is not in the vocabulary
+    NotFound = 4,
+    /// A file could not be read
+    Io = 5,
+    /// The model data is malformed or was written in an unsupported format version
+    Model = 6,
+    /// An internal error occurred
+    Panic = 7,
+}
+
+/// A segmenter holding the word scores
+pub struct InstantSegmentSegmenter(Segmenter);
+
+/// Buffers used while segmenting, which can be reused between calls
+pub struct InstantSegmentSearch(Search);
+
+/// The byte range of a word in the input
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct InstantSegmentSpan {
+    /// Offset of the first byte of the word
+    pub start: usize,
+    /// Offset just past the last byte of the word
+    pub end: usize,
+}
+
+/// Load a segmenter from the model file at `path`
+///
+/// Accepts the files written by `Segmenter::save()` and `Segmenter::save_compact()`, optionally
+/// gzip compressed. On success, the segmenter is written to `out` and must be released with
+/// `instant_segment_segmenter_free()`.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_segmenter_load(
+    path: *const c_char,
+    out: *mut *mut InstantSegmentSegmenter,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let file = std::fs::File::open(c_str(path)?).map_err(ModelError::from);
+        let segmenter = file.and_then(Segmenter::load).map_err(model_error)?;
+        write(out, boxed(segmenter))
+    })
+}
+
+/// Load a segmenter from the `len` bytes of model data at `data`
+///
+/// Like `instant_segment_segmenter_load()`, but for model data already in memory.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_segmenter_load_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut InstantSegmentSegmenter,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let segmenter = Segmenter::load(bytes(data, len)?).map_err(model_error)?;
+        write(out, boxed(segmenter))
+    })
+}
+
+/// Create a segmenter from unigram and bigram count files
+///
+/// The files contain one word or pair of words per line, followed by a tab and the count, like
+/// the files in the `data` directory of the repository.
+///
+/// # Safety
+///
+/// `unigrams` and `bigrams` must be NUL-terminated strings and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_segmenter_from_files(
+    unigrams: *const c_char,
+    bigrams: *const c_char,
+    out: *mut *mut InstantSegmentSegmenter,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let (unigrams, bigrams) = (c_str(unigrams)?, c_str(bigrams)?);
+        let segmenter = Segmenter::from_files(unigrams, bigrams).map_err(model_error)?;
+        write(out, boxed(segmenter))
+    })
+}
+
+/// Release a segmenter
+///
+/// # Safety
+///
+/// `segmenter` must be null or a segmenter that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_segmenter_free(segmenter: *mut InstantSegmentSegmenter) {
+    if !segmenter.is_null() {
+        drop(Box::from_raw(segmenter));
+    }
+}
+
+/// Allocate a search, which must be released with `instant_segment_search_free()`
+#[no_mangle]
+pub extern "C" fn instant_segment_search_new() -> *mut InstantSegmentSearch {
+    Box::into_raw(Box::new(InstantSegmentSearch(Search::default())))
+}
+
+/// Release a search
+///
+/// # Safety
+///
+/// `search` must be null or a search that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_search_free(search: *mut InstantSegmentSearch) {
+    if !search.is_null() {
+        drop(Box::from_raw(search));
+    }
+}
+
+/// Segment the `input_len` bytes of text at `input` into words
+///
+/// The spans of the words are written to `spans`, which has room for `capacity` spans, and the
+/// number of words to `len`. If there are more words than fit, `len` is set to the number of
+/// words and `INSTANT_SEGMENT_STATUS_BUFFER_TOO_SMALL` is returned, so the call can be repeated
+/// with a larger buffer. If `score` is not null, the score of the segmentation is written to it.
+/// Input that is not valid UTF-8 results in `INSTANT_SEGMENT_STATUS_INVALID_CHARACTER`.
+///
+/// # Safety
+///
+/// `segmenter` and `search` must be valid, `input` must be valid for reads of `input_len` bytes,
+/// `spans` must be valid for writes of `capacity` spans, and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_segment(
+    segmenter: *const InstantSegmentSegmenter,
+    search: *mut InstantSegmentSearch,
+    input: *const c_char,
+    input_len: usize,
+    spans: *mut InstantSegmentSpan,
+    capacity: usize,
+    len: *mut usize,
+    score: *mut f64,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let segmenter = &segmenter
+            .as_ref()
+            .ok_or(InstantSegmentStatus::InvalidArgument)?
+            .0;
+        let search = &mut search
+            .as_mut()
+            .ok_or(InstantSegmentStatus::InvalidArgument)?
+            .0;
+        let input = bytes(input as *const u8, input_len)?;
+        let input = str::from_utf8(input).map_err(|_| InstantSegmentStatus::InvalidCharacter)?;
+
+        let segments = segmenter
+            .segment(input, search)
+            .map_err(|_| InstantSegmentStatus::InvalidCharacter)?;
+        write(len, segments.len())?;
+        if segments.len() > capacity {
+            return Err(InstantSegmentStatus::BufferTooSmall);
+        }
+
+        if !score.is_null() {
+            *score = segments.score();
+        }
+
+        if segments.len() == 0 {
+            return Ok(());
+        } else if spans.is_null() {
+            return Err(InstantSegmentStatus::InvalidArgument);
+        }
+
+        let spans = slice::from_raw_parts_mut(spans, segments.len());
+        let mut start = 0;
+        for (span, word) in spans.iter_mut().zip(segments) {
+            *span = InstantSegmentSpan {
+                start,
+                end: start + word.len(),
+            };
+            start = span.end;
+        }
+
+        Ok(())
+    })
+}
+
+/// Score the sentence made up of the `len` NUL-terminated `words`
+///
+/// Returns `INSTANT_SEGMENT_STATUS_INVALID_ARGUMENT` if there are no words.
+///
+/// # Safety
+///
+/// `segmenter` must be valid, `words` must be valid for reads of `len` NUL-terminated strings,
+/// and `score` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_score_sentence(
+    segmenter: *const InstantSegmentSegmenter,
+    words: *const *const c_char,
+    len: usize,
+    score: *mut f64,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let segmenter = &segmenter
+            .as_ref()
+            .ok_or(InstantSegmentStatus::InvalidArgument)?
+            .0;
+        let words = match len {
+            0 => &[],
+            _ if words.is_null() => return Err(InstantSegmentStatus::InvalidArgument),
+            _ => slice::from_raw_parts(words, len),
+        };
+
+        let words = words
+            .iter()
+            .map(|&word| c_str(word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sentence = segmenter.score_sentence(words.into_iter());
+        write(
+            score,
+            sentence.ok_or(InstantSegmentStatus::InvalidArgument)?,
+        )
+    })
+}
+
+/// Look up the base-10 logarithm of the relative frequency of the NUL-terminated `word`
+///
+/// Returns `INSTANT_SEGMENT_STATUS_NOT_FOUND` if the word is not in the vocabulary.
+///
+/// # Safety
+///
+/// `segmenter` must be valid, `word` must be a NUL-terminated string, and `score` must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn instant_segment_unigram_log_prob(
+    segmenter: *const InstantSegmentSegmenter,
+    word: *const c_char,
+    score: *mut f64,
+) -> InstantSegmentStatus {
+    guard(|| {
+        let segmenter = &segmenter
+            .as_ref()
+            .ok_or(InstantSegmentStatus::InvalidArgument)?
+            .0;
+        let uni = segmenter.unigram_log_prob(c_str(word)?);
+        write(score, uni.ok_or(InstantSegmentStatus::NotFound)?)
+    })
+}
+
+/// A NUL-terminated description of `status`, which must not be released
+#[no_mangle]
+pub extern "C" fn instant_segment_status_message(status: InstantSegmentStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        InstantSegmentStatus::Ok => b"ok\0",
+        InstantSegmentStatus::InvalidArgument => b"invalid argument\0",
+        InstantSegmentStatus::InvalidCharacter => b"invalid character in input\0",
+        InstantSegmentStatus::BufferTooSmall => b"buffer too small\0",
+        InstantSegmentStatus::NotFound => b"word not found\0",
+        InstantSegmentStatus::Io => b"I/O error\0",
+        InstantSegmentStatus::Model => b"invalid model data\0",
+        InstantSegmentStatus::Panic => b"internal error\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// Run `f`, converting its result and any panic into a status
+fn guard(f: impl FnOnce() -> Result<(), InstantSegmentStatus>) -> InstantSegmentStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => InstantSegmentStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => InstantSegmentStatus::Panic,
+    }
+}
+
+fn model_error(e: ModelError) -> InstantSegmentStatus {
+    match e {
+        ModelError::Io(_) => InstantSegmentStatus::Io,
+        _ => InstantSegmentStatus::Model,
+    }
+}
+
+fn boxed(segmenter: Segmenter) -> *mut InstantSegmentSegmenter {
+    Box::into_raw(Box::new(InstantSegmentSegmenter(segmenter)))
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), InstantSegmentStatus> {
+    match out.is_null() {
+        true => Err(InstantSegmentStatus::InvalidArgument),
+        false => {
+            ptr::write(out, value);
+            Ok(())
+        }
+    }
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], InstantSegmentStatus> {
+    match (data.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(InstantSegmentStatus::InvalidArgument),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+unsafe fn c_str<'a>(s: *const c_char) -> Result<&'a str, InstantSegmentStatus> {
+    if s.is_null() {
+        return Err(InstantSegmentStatus::InvalidArgument);
+    }
+
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| InstantSegmentStatus::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instant_segment::model::Metadata;
+
+    #[test]
+    fn test_segment() {
+        let segmenter = instant_segment::test_cases::choose_spain();
+        let mut model = Vec::new();
+        segmenter.save(&mut model, &Metadata::default()).unwrap();
+
+        unsafe {
+            let mut segmenter = ptr::null_mut();
+            let status =
+                instant_segment_segmenter_load_bytes(model.as_ptr(), model.len(), &mut segmenter);
+            assert_eq!(status, InstantSegmentStatus::Ok);
+            let search = instant_segment_search_new();
+
+            let input = "choosespain";
+            let segment = |spans: &mut [InstantSegmentSpan], len: &mut usize, score| {
+                instant_segment_segment(
+                    segmenter,
+                    search,
+                    input.as_ptr() as *const c_char,
+                    input.len(),
+                    spans.as_mut_ptr(),
+                    spans.len(),
+                    len,
+                    score,
+                )
+            };
+
+            let (mut len, mut score) = (0, 0.0);
+            let mut spans = [InstantSegmentSpan::default(); 1];
+            let status = segment(&mut spans, &mut len, ptr::null_mut());
+            assert_eq!(status, InstantSegmentStatus::BufferTooSmall);
+            assert_eq!(len, 2);
+
+            let mut spans = [InstantSegmentSpan::default(); 4];
+            let status = segment(&mut spans, &mut len, &mut score);
+            assert_eq!(status, InstantSegmentStatus::Ok);
+            assert_eq!(len, 2);
+            assert_eq!(spans[0], InstantSegmentSpan { start: 0, end: 6 });
+            assert_eq!(spans[1], InstantSegmentSpan { start: 6, end: 11 });
+
+            let words = [b"choose\0".as_ptr(), b"spain\0".as_ptr()];
+            let mut sentence = 0.0;
+            let status = instant_segment_score_sentence(
+                segmenter,
+                words.as_ptr() as *const *const c_char,
+                words.len(),
+                &mut sentence,
+            );
+            assert_eq!(status, InstantSegmentStatus::Ok);
+            assert!((sentence - score).abs() < 1e-9);
+
+            let mut uni = 0.0;
+            let word = b"xyzzy\0".as_ptr() as *const c_char;
+            let status = instant_segment_unigram_log_prob(segmenter, word, &mut uni);
+            assert_eq!(status, InstantSegmentStatus::NotFound);
+
+            for input in [&b"Spain"[..], &b"spain\xff"[..]] {
+                let status = instant_segment_segment(
+                    segmenter,
+                    search,
+                    input.as_ptr() as *const c_char,
+                    input.len(),
+                    ptr::null_mut(),
+                    0,
+                    &mut len,
+                    ptr::null_mut(),
+                );
+                assert_eq!(status, InstantSegmentStatus::InvalidCharacter);
+            }
+
+            let status = instant_segment_segmenter_load_bytes(b"model".as_ptr(), 5, &mut segmenter);
+            assert_eq!(status, InstantSegmentStatus::Model);
+
+            instant_segment_search_free(search);
+            instant_segment_segmenter_free(segmenter);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Check that the checked-in header matches the API, set `UPDATE_HEADER` to regenerate it
+#[test]
+fn header() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).unwrap();
+    let mut header = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(dir)
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut header);
+
+    let path = dir.join("include/instant_segment.h");
+    if env::var_os("UPDATE_HEADER").is_some() {
+        fs::write(&path, &header).unwrap();
+    }
+
+    let existing = fs::read(&path).unwrap_or_default();
+    assert!(
+        existing == header,
+        "{} is out of date, run the tests with UPDATE_HEADER=1 to regenerate it",
+        path.display()
+    );
+}
@@ -0,0 +1,72 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "instant_segment.h"
+
+static void check(InstantSegmentStatus status, InstantSegmentStatus expected) {
+    if (status != expected) {
+        fprintf(stderr, "expected '%s', got '%s'\n", instant_segment_status_message(expected),
+                instant_segment_status_message(status));
+        exit(1);
+    }
+}
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s UNIGRAMS BIGRAMS\n", argv[0]);
+        return 2;
+    }
+
+    InstantSegmentSegmenter *segmenter = NULL;
+    check(instant_segment_segmenter_from_files(argv[1], argv[2], &segmenter),
+          INSTANT_SEGMENT_STATUS_OK);
+    InstantSegmentSearch *search = instant_segment_search_new();
+
+    const char *input = "thisisatest";
+    InstantSegmentSpan spans[8];
+    size_t len = 0;
+    double score = 0.0;
+    check(instant_segment_segment(segmenter, search, input, strlen(input), spans, 2, &len, NULL),
+          INSTANT_SEGMENT_STATUS_BUFFER_TOO_SMALL);
+    assert(len == 4);
+    check(instant_segment_segment(segmenter, search, input, strlen(input), spans, 8, &len, &score),
+          INSTANT_SEGMENT_STATUS_OK);
+
+    const char *expected[] = {"this", "is", "a", "test"};
+    assert(len == 4);
+    for (size_t i = 0; i < len; i++) {
+        size_t n = spans[i].end - spans[i].start;
+        assert(n == strlen(expected[i]));
+        assert(strncmp(input + spans[i].start, expected[i], n) == 0);
+    }
+
+    double sentence = 0.0;
+    check(instant_segment_score_sentence(segmenter, expected, 4, &sentence),
+          INSTANT_SEGMENT_STATUS_OK);
+    assert(sentence == score);
+
+    double uni = 0.0;
+    check(instant_segment_unigram_log_prob(segmenter, "test", &uni), INSTANT_SEGMENT_STATUS_OK);
+    assert(uni < 0.0);
+    check(instant_segment_unigram_log_prob(segmenter, "xyzzyxyzzy", &uni),
+          INSTANT_SEGMENT_STATUS_NOT_FOUND);
+
+    input = "This is a test";
+    check(instant_segment_segment(segmenter, search, input, strlen(input), spans, 8, &len, NULL),
+          INSTANT_SEGMENT_STATUS_INVALID_CHARACTER);
+    input = "test\xff\xfe";
+    check(instant_segment_segment(segmenter, search, input, strlen(input), spans, 8, &len, NULL),
+          INSTANT_SEGMENT_STATUS_INVALID_CHARACTER);
+    check(instant_segment_unigram_log_prob(segmenter, "\xfftest", &uni),
+          INSTANT_SEGMENT_STATUS_INVALID_ARGUMENT);
+
+    InstantSegmentSegmenter *missing = NULL;
+    check(instant_segment_segmenter_load("does-not-exist.bin", &missing), INSTANT_SEGMENT_STATUS_IO);
+    assert(missing == NULL);
+
+    instant_segment_search_free(search);
+    instant_segment_segmenter_free(segmenter);
+    printf("ok\n");
+    return 0;
+}
@@ -1,19 +1,76 @@
-from collections.abc import Iterator
+from collections.abc import Iterable, Iterator
+from os import PathLike
+from typing import Literal, Optional, TypedDict
+
+class InvalidCharacterError(ValueError):
+    position: int
+    character: str
+
+class ModelFormatError(ValueError): ...
+class ModelVersionError(ModelFormatError): ...
+
+class ModelInfo(TypedDict):
+    version: int
+    source: str
+    build_date: str
+    smoothing: str
+    vocabulary_size: int
+    bigrams: int
+    limit: int
+    unknown_base: float
+    unknown_word_len: float
+    bigram_weight: float
+    compact: bool
 
 class Search:
     def __new__(cls, /) -> Search: ...
     def __iter__(self, /) -> Iterator[str]: ...
     def __next__(self, /) -> str: ...
 
+class Segmentation:
+    @property
+    def words(self) -> list[str]: ...
+    @property
+    def spans(self) -> list[tuple[int, int]]: ...
+    @property
+    def score(self) -> float: ...
+
 class Segmenter:
     @staticmethod
     def load(fname: str) -> Segmenter: ...
+    @staticmethod
+    def from_files(
+        unigrams_path: str | PathLike[str], bigrams_path: str | PathLike[str]
+    ) -> Segmenter: ...
+    @staticmethod
+    def default_english() -> Segmenter: ...
+    @staticmethod
+    def from_bytes(data: bytes) -> Segmenter: ...
     def __new__(
         cls,
         /,
-        unigrams: Iterator[tuple[str, float]],
-        bigrams: Iterator[tuple[tuple[str, str], float]],
+        unigrams: Iterable[tuple[str, float]],
+        bigrams: Iterable[tuple[tuple[str, str], float]],
     ) -> Segmenter: ...
-    def dump(self, /, fname: str) -> None: ...
-    def score_sentence(self, /, words: Iterator[str]) -> float: ...
+    @staticmethod
+    def model_info(fname: str) -> ModelInfo: ...
+    def dump(
+        self,
+        /,
+        fname: str,
+        *,
+        source: str = "",
+        build_date: str = "",
+        smoothing: str = "",
+        compression: Optional[Literal["gzip", "zstd"]] = None,
+        compact: bool = False,
+    ) -> None: ...
+    def to_bytes(self, /) -> bytes: ...
+    def score_sentence(self, /, words: Iterator[str]) -> Optional[float]: ...
     def segment(self, /, s: str, search: Search) -> float: ...
+    def segment_words(self, /, s: str) -> list[str]: ...
+    def segment_detailed(self, /, s: str) -> Segmentation: ...
+    def segment_n_best(self, /, s: str, k: int) -> list[Segmentation]: ...
+    def segment_batch(
+        self, /, inputs: list[str], threads: Optional[int] = None
+    ) -> list[list[str]]: ...
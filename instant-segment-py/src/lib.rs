@@ -1,26 +1,68 @@
 // borrow_deref_ref doesn't get macro detection right, allow for now
 #![allow(clippy::from_iter_instead_of_collect, clippy::borrow_deref_ref)]
 
+use std::cell::RefCell;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufRead, BufReader, BufWriter, Read};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
+use bincode::Options;
+use instant_segment::compression::{self, Compression, Encoder};
+use instant_segment::model::{self, Metadata};
+use instant_segment::ModelError;
+use pyo3::create_exception;
+#[cfg(not(feature = "english"))]
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::exceptions::PyValueError;
 use pyo3::pybacked::PyBackedStr;
-use pyo3::types::{PyAnyMethods, PyIterator, PyModule, PyModuleMethods};
+use pyo3::types::{
+    PyAny, PyAnyMethods, PyBytes, PyDict, PyDictMethods, PyIterator, PyModule, PyModuleMethods,
+};
 use pyo3::{pyclass, pymethods, pymodule, Bound};
 use pyo3::{PyErr, PyRef, PyRefMut, PyResult, Python};
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use smartstring::alias::String as SmartString;
 
 #[pymodule]
 #[pyo3(name = "instant_segment")]
 fn instant_segment_py(_: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Search>()?;
+    m.add_class::<Segmentation>()?;
     m.add_class::<Segmenter>()?;
+    let py = m.py();
+    m.add(
+        "InvalidCharacterError",
+        py.get_type::<InvalidCharacterError>(),
+    )?;
+    m.add("ModelFormatError", py.get_type::<ModelFormatError>())?;
+    m.add("ModelVersionError", py.get_type::<ModelVersionError>())?;
     Ok(())
 }
 
+create_exception!(
+    instant_segment,
+    InvalidCharacterError,
+    PyValueError,
+    "Raised when the input contains characters other than lowercase ASCII letters and digits\n\n\
+     The `position` attribute holds the index of the first invalid character, the \
+     `character` attribute holds the character itself."
+);
+create_exception!(
+    instant_segment,
+    ModelFormatError,
+    PyValueError,
+    "Raised when model data is malformed or corrupted"
+);
+create_exception!(
+    instant_segment,
+    ModelVersionError,
+    ModelFormatError,
+    "Raised when model data was written in an unsupported format version"
+);
+
 /// Segmenter holding the word lists
-#[pyclass]
+#[pyclass(module = "instant_segment")]
 struct Segmenter {
     inner: instant_segment::Segmenter,
 }
@@ -30,9 +72,9 @@ impl Segmenter {
     /// Build a segmenter from `unigrams` and `bigrams` iterators
     ///
     /// The `unigrams` iterator should yield `(str, float)` items, while the `bigrams`
-    /// iterator should yield `((str, str), float)` items.
+    /// iterator should yield `((str, str), float)` items. Any iterable is accepted.
     #[new]
-    fn new(unigrams: &Bound<'_, PyIterator>, bigrams: &Bound<'_, PyIterator>) -> PyResult<Self> {
+    fn new(unigrams: &Bound<'_, PyAny>, bigrams: &Bound<'_, PyAny>) -> PyResult<Self> {
         let unigrams = unigrams
             .try_iter()?
             .map(|result| {
@@ -66,22 +108,147 @@ impl Segmenter {
         })
     }
 
+    /// Build a segmenter from unigram and bigram count files
+    ///
+    /// Unigram files contain a word and its count on each line, separated by a tab. Bigram
+    /// files contain two words separated by a space, followed by a tab and the count. The files
+    /// are parsed without holding the GIL.
+    #[staticmethod]
+    fn from_files(py: Python<'_>, unigrams_path: PathBuf, bigrams_path: PathBuf) -> PyResult<Self> {
+        let inner = py
+            .detach(|| instant_segment::Segmenter::from_files(unigrams_path, bigrams_path))
+            .map_err(model_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Build a segmenter from the English word counts bundled with this package
+    ///
+    /// The bundled model has unigram counts only, no bigrams. Parsing it takes tens of
+    /// milliseconds, so the result should be reused.
+    #[staticmethod]
+    fn default_english(py: Python<'_>) -> PyResult<Self> {
+        #[cfg(feature = "english")]
+        {
+            let inner = py.detach(instant_segment::Segmenter::english);
+            Ok(Self { inner })
+        }
+
+        #[cfg(not(feature = "english"))]
+        {
+            let _ = py;
+            Err(PyRuntimeError::new_err(
+                "instant-segment was built without the bundled English model",
+            ))
+        }
+    }
+
+    /// Build a segmenter from bytes produced by `to_bytes()`
+    #[staticmethod]
+    fn from_bytes(py: Python<'_>, data: &[u8]) -> PyResult<Self> {
+        let inner = py.detach(|| read_model(data))?;
+        Ok(Self { inner })
+    }
+
+    /// Serialize the segmenter to bytes, which can be loaded with `from_bytes()`
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut data = Vec::new();
+        py.detach(|| self.inner.save(&mut data, &Metadata::default()))
+            .map_err(model_error)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.to_bytes(py)
+    }
+
+    fn __setstate__(&mut self, py: Python<'_>, state: &[u8]) -> PyResult<()> {
+        *self = Self::from_bytes(py, state)?;
+        Ok(())
+    }
+
+    /// Pickling creates an empty segmenter first, then restores the state into it
+    fn __getnewargs__(&self) -> ((), ()) {
+        ((), ())
+    }
+
     /// Load a segmenter from the given file name
+    ///
+    /// Files written by `dump()` in older releases can still be loaded.
     #[staticmethod]
-    fn load(fname: &str) -> PyResult<Self> {
-        let hnsw = bincode::deserialize_from::<_, instant_segment::Segmenter>(
-            BufReader::with_capacity(32 * 1024 * 1024, File::open(fname)?),
-        )
-        .map_err(|e| PyValueError::new_err(format!("deserialization error: {e:?}")))?;
-        Ok(Self { inner: hnsw })
+    fn load(py: Python<'_>, fname: &str) -> PyResult<Self> {
+        let f = BufReader::with_capacity(32 * 1024 * 1024, File::open(fname)?);
+        let inner = py.detach(|| read_model(f))?;
+        Ok(Self { inner })
+    }
+
+    /// Read the metadata and statistics from the header of a model file
+    ///
+    /// Returns a dict with the `version`, `source`, `build_date`, `smoothing`,
+    /// `vocabulary_size`, `bigrams`, `limit`, `unknown_base`, `unknown_word_len`,
+    /// `bigram_weight` and `compact` keys.
+    #[staticmethod]
+    fn model_info<'py>(py: Python<'py>, fname: &str) -> PyResult<Bound<'py, PyDict>> {
+        let info = model::read_info(BufReader::new(File::open(fname)?)).map_err(model_error)?;
+        let dict = PyDict::new(py);
+        dict.set_item("version", info.version)?;
+        dict.set_item("source", info.metadata.source)?;
+        dict.set_item("build_date", info.metadata.build_date)?;
+        dict.set_item("smoothing", info.metadata.smoothing)?;
+        dict.set_item("vocabulary_size", info.vocabulary_size)?;
+        dict.set_item("bigrams", info.bigrams)?;
+        dict.set_item("limit", info.limit)?;
+        dict.set_item("unknown_base", info.params.unknown_base)?;
+        dict.set_item("unknown_word_len", info.params.unknown_word_len)?;
+        dict.set_item("bigram_weight", info.params.bigram_weight)?;
+        dict.set_item("compact", info.compact)?;
+        Ok(dict)
     }
 
     /// Dump the segmenter to the given file name
-    fn dump(&self, fname: &str) -> PyResult<()> {
+    ///
+    /// The `source`, `build_date` and `smoothing` descriptions are stored in the file and can
+    /// be read back with `model_info()`. Set `compression` to `"gzip"` or `"zstd"` to compress
+    /// the file; compressed files are detected automatically when loading. Set `compact` to
+    /// write the smaller compact encoding, which stores the scores with less precision.
+    #[pyo3(signature = (fname, *, source=String::new(), build_date=String::new(), smoothing=String::new(), compression=None, compact=false))]
+    #[allow(clippy::too_many_arguments)] // Keyword arguments from Python
+    fn dump(
+        &self,
+        py: Python<'_>,
+        fname: &str,
+        source: String,
+        build_date: String,
+        smoothing: String,
+        compression: Option<&str>,
+        compact: bool,
+    ) -> PyResult<()> {
+        let compression = match compression {
+            None => Compression::None,
+            Some("gzip") => Compression::Gzip,
+            Some("zstd") => Compression::Zstd,
+            Some(other) => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown compression {other:?}, expected 'gzip' or 'zstd'"
+                )))
+            }
+        };
+
+        let metadata = Metadata {
+            source,
+            build_date,
+            smoothing,
+        };
         let f = BufWriter::with_capacity(32 * 1024 * 1024, File::create(fname)?);
-        bincode::serialize_into(f, &self.inner)
-            .map_err(|e| PyValueError::new_err(format!("serialization error: {e:?}")))?;
-        Ok(())
+        py.detach(|| {
+            let mut encoder = Encoder::new(f, compression)?;
+            match compact {
+                true => self.inner.save_compact(&mut encoder, &metadata)?,
+                false => self.inner.save(&mut encoder, &metadata)?,
+            }
+            encoder.finish()?;
+            Ok(())
+        })
+        .map_err(model_error)
     }
 
     /// Segment the given str `s`
@@ -90,29 +257,159 @@ impl Segmenter {
     /// iterate over the `Search` to get the resulting words.
     ///
     /// For best performance, reusing `Search` objects is recommended.
-    fn segment(&self, s: &str, search: &mut Search) -> PyResult<f64> {
-        match self.inner.segment(s, &mut search.inner) {
-            Ok(iter) => {
-                let score = iter.score();
+    fn segment(&self, py: Python<'_>, s: &str, search: &mut Search) -> PyResult<f64> {
+        let inner = &mut search.inner;
+        match py.detach(|| self.inner.segment(s, inner).map(|iter| iter.score())) {
+            Ok(score) => {
                 search.cur = Some(0);
                 Ok(score)
             }
-            Err(_) => Err(PyValueError::new_err(
-                "only lowercase ASCII letters allowed",
-            )),
+            Err(e) => Err(invalid_character(s, e)),
         }
     }
 
+    /// Segment the given str `s`, returning the list of words
+    fn segment_words(&self, py: Python<'_>, s: &str) -> PyResult<Vec<String>> {
+        py.detach(|| {
+            SEARCH.with(|search| {
+                let mut search = search.borrow_mut();
+                let words = self.inner.segment(s, &mut search)?;
+                Ok(words.map(String::from).collect())
+            })
+        })
+        .map_err(|e| invalid_character(s, e))
+    }
+
+    /// Segment the given str `s`, returning the words, their spans and the score
+    fn segment_detailed(&self, py: Python<'_>, s: &str) -> PyResult<Segmentation> {
+        py.detach(|| {
+            SEARCH.with(|search| {
+                let mut search = search.borrow_mut();
+                let words = self.inner.segment(s, &mut search)?;
+                Ok(instant_segment::Segmentation::from(words).into())
+            })
+        })
+        .map_err(|e| invalid_character(s, e))
+    }
+
+    /// Find the `k` best segmentations of the given str `s`, ordered from best to worst
+    ///
+    /// Raises a `ValueError` if `k` is larger than 1000.
+    fn segment_n_best(&self, py: Python<'_>, s: &str, k: usize) -> PyResult<Vec<Segmentation>> {
+        if k > instant_segment::MAX_N_BEST {
+            return Err(PyValueError::new_err("k must be at most 1000"));
+        }
+
+        py.detach(|| {
+            SEARCH.with(|search| {
+                let mut search = search.borrow_mut();
+                let results = self.inner.segment_n_best(s, &mut search, k)?;
+                Ok(results.into_iter().map(Segmentation::from).collect())
+            })
+        })
+        .map_err(|e| invalid_character(s, e))
+    }
+
+    /// Segment all strings in `inputs` in parallel, returning a list of words for each
+    ///
+    /// Segmentation happens on a pool of `threads` threads (or one thread per CPU core if not
+    /// given) without holding the GIL. The pool is kept for reuse by later calls with the same
+    /// number of threads. Raises an error if `threads` is 0 or any of the inputs is invalid.
+    #[pyo3(signature = (inputs, threads=None))]
+    fn segment_batch(
+        &self,
+        py: Python<'_>,
+        inputs: Vec<PyBackedStr>,
+        threads: Option<usize>,
+    ) -> PyResult<Vec<Vec<String>>> {
+        if threads == Some(0) {
+            return Err(PyValueError::new_err("threads must be at least 1"));
+        }
+
+        let results = py.detach(|| {
+            let inputs = inputs.iter().map(|s| &**s).collect::<Vec<_>>();
+            match threads {
+                Some(threads) => thread_pool(threads)
+                    .map(|pool| pool.install(|| self.inner.segment_batch(&inputs)))
+                    .map_err(|e| PyValueError::new_err(format!("thread pool error: {e}"))),
+                None => Ok(self.inner.segment_batch(&inputs)),
+            }
+        })?;
+
+        results
+            .into_iter()
+            .zip(&inputs)
+            .map(|(result, s)| match result {
+                Ok(segmentation) => Ok(segmentation
+                    .words()
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect()),
+                Err(e) => Err(invalid_character(s, e)),
+            })
+            .collect()
+    }
+
     /// Returns the sentence's score
     ///
     /// Returns the relative probability for the given sentence in the the corpus represented by
     /// this `Segmenter`. Will return `None` iff given an empty iterator argument.
-    fn score_sentence(&self, words: &Bound<'_, PyIterator>) -> PyResult<Option<f64>> {
+    fn score_sentence(
+        &self,
+        py: Python<'_>,
+        words: &Bound<'_, PyIterator>,
+    ) -> PyResult<Option<f64>> {
         let words = words
             .try_iter()?
             .map(|result| result?.extract::<PyBackedStr>())
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(self.inner.score_sentence(words.iter().map(|s| &**s)))
+        Ok(py.detach(|| self.inner.score_sentence(words.iter().map(|s| &**s))))
+    }
+}
+
+/// Result of a segmentation
+#[pyclass(get_all, frozen)]
+struct Segmentation {
+    /// The words found in the segmented text
+    words: Vec<String>,
+    /// Start and end offsets of the words in the segmented text
+    spans: Vec<(usize, usize)>,
+    /// The score of the segmented text
+    score: f64,
+}
+
+#[pymethods]
+impl Segmentation {
+    fn __repr__(&self) -> String {
+        let words = self
+            .words
+            .iter()
+            .map(|word| format!("'{word}'"))
+            .collect::<Vec<_>>();
+        format!(
+            "Segmentation(words=[{}], spans={:?}, score={:?})",
+            words.join(", "),
+            self.spans,
+            self.score
+        )
+    }
+}
+
+impl From<instant_segment::Segmentation> for Segmentation {
+    fn from(segmentation: instant_segment::Segmentation) -> Self {
+        Self {
+            words: segmentation
+                .words()
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+            spans: segmentation
+                .spans()
+                .iter()
+                .map(|span| (span.start, span.end))
+                .collect(),
+            score: segmentation.score(),
+        }
     }
 }
 
@@ -157,3 +454,77 @@ impl Search {
         Some(word)
     }
 }
+
+/// A thread pool with `threads` threads, shared by all segmenters
+///
+/// Building a pool spawns its threads, so the last pool is kept for reuse and only replaced when
+/// a different number of threads is requested. A replaced pool shuts down once the calls still
+/// using it have finished.
+fn thread_pool(threads: usize) -> Result<Arc<ThreadPool>, rayon::ThreadPoolBuildError> {
+    static POOL: Mutex<Option<Arc<ThreadPool>>> = Mutex::new(None);
+    let mut pool = POOL.lock().unwrap();
+    match &*pool {
+        Some(pool) if pool.current_num_threads() == threads => Ok(pool.clone()),
+        _ => {
+            let new = Arc::new(ThreadPoolBuilder::new().num_threads(threads).build()?);
+            *pool = Some(new.clone());
+            Ok(new)
+        }
+    }
+}
+
+/// Read a model in the versioned format, falling back to the format used by older releases
+fn read_model(reader: impl BufRead) -> PyResult<instant_segment::Segmenter> {
+    // A single `fill_buf()` may return fewer bytes than the magic number, so read it in full
+    let mut reader = compression::decode(reader).map_err(model_error)?;
+    let mut start = Vec::with_capacity(model::MAGIC.len());
+    (&mut reader)
+        .take(model::MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    let mut reader = start.as_slice().chain(reader);
+    if start == model::MAGIC {
+        return instant_segment::Segmenter::load(reader).map_err(model_error);
+    }
+
+    // Older releases dumped the bincode serialization of the `Segmenter`, which didn't include
+    // the scoring parameters yet. Bincode can't detect missing fields, so append the defaults.
+    // Limiting the size to that of the data keeps garbage from causing huge allocations.
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    options
+        .serialize_into(&mut data, &instant_segment::Params::default())
+        .and_then(|_| options.with_limit(data.len() as u64).deserialize(&data))
+        .map_err(|e| ModelFormatError::new_err(format!("invalid model data: {e}")))
+}
+
+fn model_error(e: ModelError) -> PyErr {
+    match e {
+        ModelError::Io(e) => e.into(),
+        e @ ModelError::UnsupportedVersion { .. } => ModelVersionError::new_err(e.to_string()),
+        e => ModelFormatError::new_err(e.to_string()),
+    }
+}
+
+/// Convert `e` for input `s`, reporting the position as an index into the Python string
+fn invalid_character(s: &str, e: instant_segment::InvalidCharacter) -> PyErr {
+    let position = s[..e.position()].chars().count();
+    Python::attach(|py| {
+        let err = InvalidCharacterError::new_err(format!(
+            "invalid character {:?} at position {position}: only lowercase ASCII letters and \
+             digits are allowed",
+            e.character()
+        ));
+        let value = err.value(py);
+        let result = value
+            .setattr("position", position)
+            .and_then(|()| value.setattr("character", e.character()));
+        result.err().unwrap_or(err)
+    })
+}
+
+thread_local! {
+    static SEARCH: RefCell<instant_segment::Search> = RefCell::new(instant_segment::Search::default());
+}
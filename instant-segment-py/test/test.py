@@ -1,4 +1,4 @@
-import instant_segment, os, sys
+import gzip, instant_segment, os, pickle, shutil, struct, sys, tempfile
 
 DATA_DIR = os.path.join(os.path.dirname(__file__), '../../data/')
 
@@ -20,5 +20,112 @@ def main():
     print(f"{score=}")
     print([word for word in search])
 
+    assert segmenter.segment_words('thisisatest') == ['this', 'is', 'a', 'test']
+    result = segmenter.segment_detailed('thisisatest')
+    assert result.words == ['this', 'is', 'a', 'test']
+    assert result.spans == [(0, 4), (4, 6), (6, 7), (7, 11)]
+    assert result.score == score
+
+    n_best = segmenter.segment_n_best('thisisatest', 3)
+    assert len(n_best) == 3
+    assert n_best[0].words == result.words
+    assert n_best[0].score >= n_best[1].score >= n_best[2].score
+    try:
+        segmenter.segment_n_best('thisisatest', 1001)
+        assert False
+    except ValueError:
+        pass
+
+    batch = segmenter.segment_batch(['thisisatest', 'choosespain', ''], threads=2)
+    assert batch == [['this', 'is', 'a', 'test'], ['choose', 'spain'], []]
+    assert segmenter.segment_batch(['thisisatest'], threads=2) == batch[:1]
+    assert segmenter.segment_batch(['thisisatest'], threads=1) == batch[:1]
+    assert segmenter.segment_batch(['thisisatest']) == batch[:1]
+    try:
+        segmenter.segment_batch(['thisisatest'], threads=0)
+        assert False
+    except ValueError:
+        pass
+
+    from_files = instant_segment.Segmenter.from_files(
+        os.path.join(DATA_DIR, 'en-unigrams.txt'),
+        os.path.join(DATA_DIR, 'en-bigrams.txt'),
+    )
+    assert from_files.segment_detailed('thisisatest').score == score
+
+    english = instant_segment.Segmenter.default_english()
+    assert english.segment_words('thisisatest') == ['this', 'is', 'a', 'test']
+
+    restored = instant_segment.Segmenter.from_bytes(segmenter.to_bytes())
+    assert restored.segment_words('thisisatest') == ['this', 'is', 'a', 'test']
+    unpickled = pickle.loads(pickle.dumps(segmenter))
+    assert unpickled.segment_detailed('thisisatest').score == score
+
+    try:
+        segmenter.segment_words('this is a test')
+        assert False
+    except instant_segment.InvalidCharacterError as e:
+        assert (e.position, e.character) == (4, ' ')
+        assert isinstance(e, ValueError)
+
+    data = segmenter.to_bytes()
+    for invalid, error in [
+        (b'garbage', instant_segment.ModelFormatError),
+        (data[:8] + b'\x63' + data[9:], instant_segment.ModelVersionError),
+        (data[:-1], instant_segment.ModelFormatError),
+        (data[:4], instant_segment.ModelFormatError),
+        (b'', instant_segment.ModelFormatError),
+    ]:
+        try:
+            instant_segment.Segmenter.from_bytes(invalid)
+            assert False
+        except error:
+            pass
+
+    # The decompressed data arrives in pieces shorter than the magic number
+    split = gzip.compress(data[:3]) + gzip.compress(data[3:])
+    restored = instant_segment.Segmenter.from_bytes(split)
+    assert restored.segment_detailed('thisisatest').score == score
+
+    # Bincode serialization written by older releases, without the scoring parameters
+    legacy = struct.pack('<QQ6sdQdQ', 1, 6, b'choose', -0.5, 0, 5.0, 24)
+    restored = instant_segment.Segmenter.from_bytes(legacy)
+    assert restored.segment_words('choose') == ['choose']
+
+    with tempfile.TemporaryDirectory() as tmp:
+        fname = os.path.join(tmp, 'en.model')
+        segmenter.dump(fname, source='test data', build_date='2026-10-18')
+        info = instant_segment.Segmenter.model_info(fname)
+        assert info['source'] == 'test data'
+        assert info['build_date'] == '2026-10-18'
+        assert info['limit'] == 24
+        assert info['bigram_weight'] == 1.0
+        assert set(info) == {
+            'version', 'source', 'build_date', 'smoothing', 'vocabulary_size', 'bigrams',
+            'limit', 'unknown_base', 'unknown_word_len', 'bigram_weight', 'compact',
+        }
+        loaded = instant_segment.Segmenter.load(fname)
+        assert loaded.segment_detailed('thisisatest').score == score
+
+        segmenter.dump(fname, compact=True)
+        assert instant_segment.Segmenter.model_info(fname)['compact']
+        loaded = instant_segment.Segmenter.load(fname)
+        assert loaded.segment_words('thisisatest') == ['this', 'is', 'a', 'test']
+
+        for compression in ['gzip', 'zstd']:
+            segmenter.dump(fname, compression=compression)
+            assert instant_segment.Segmenter.model_info(fname)['limit'] == 24
+            loaded = instant_segment.Segmenter.load(fname)
+            assert loaded.segment_detailed('thisisatest').score == score
+
+        unigrams_gz = os.path.join(tmp, 'en-unigrams.txt.gz')
+        with open(os.path.join(DATA_DIR, 'en-unigrams.txt'), 'rb') as src:
+            with gzip.open(unigrams_gz, 'wb') as dst:
+                shutil.copyfileobj(src, dst)
+        from_gz = instant_segment.Segmenter.from_files(
+            unigrams_gz, os.path.join(DATA_DIR, 'en-bigrams.txt')
+        )
+        assert from_gz.segment_detailed('thisisatest').score == score
+
 if __name__ == '__main__':
     main()
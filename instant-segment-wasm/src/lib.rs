@@ -0,0 +1,127 @@
+//! JavaScript bindings for Instant Segment
+//!
+//! Segmenters are loaded from the bytes of a binary model file. Models written with
+//! `Segmenter::save_compact()` are the best fit for downloading to a browser.
+
+use instant_segment::Search;
+use wasm_bindgen::prelude::*;
+
+/// Segmenter holding the word lists
+#[wasm_bindgen]
+pub struct Segmenter {
+    inner: instant_segment::Segmenter,
+    search: Search,
+}
+
+#[wasm_bindgen]
+impl Segmenter {
+    /// Load a segmenter from the bytes of a model file
+    ///
+    /// Compressed model files are not supported; serve the model with HTTP compression instead.
+    #[wasm_bindgen(constructor)]
+    pub fn new(model: &[u8]) -> Result<Segmenter, JsError> {
+        Ok(Self {
+            inner: instant_segment::Segmenter::load(model)?,
+            search: Search::default(),
+        })
+    }
+
+    /// Segment `input` into words
+    ///
+    /// Throws if `input` contains characters other than lowercase ASCII letters and digits; use
+    /// `clean()` to prepare arbitrary text.
+    pub fn segment(&mut self, input: &str) -> Result<Vec<String>, JsError> {
+        let words = self.inner.segment(input, &mut self.search)?;
+        Ok(words.map(String::from).collect())
+    }
+
+    /// Find the `n` best segmentations of `input`, ordered from best to worst
+    ///
+    /// Throws if `n` is larger than 1000.
+    #[wasm_bindgen(js_name = segmentNBest)]
+    pub fn segment_n_best(&mut self, input: &str, n: usize) -> Result<Vec<Segmentation>, JsError> {
+        if n > instant_segment::MAX_N_BEST {
+            return Err(JsError::new("n must be at most 1000"));
+        }
+
+        let results = self.inner.segment_n_best(input, &mut self.search, n)?;
+        Ok(results
+            .iter()
+            .map(|segmentation| Segmentation {
+                words: segmentation.words().iter().map(|w| w.to_string()).collect(),
+                score: segmentation.score(),
+            })
+            .collect())
+    }
+
+    /// The score of the sentence made up of `words`, or `undefined` if there are no words
+    #[wasm_bindgen(js_name = scoreSentence)]
+    pub fn score_sentence(&self, words: Vec<String>) -> Option<f64> {
+        self.inner
+            .score_sentence(words.iter().map(|word| word.as_str()))
+    }
+
+    /// The number of words in the vocabulary
+    #[wasm_bindgen(getter, js_name = vocabLength)]
+    pub fn vocab_len(&self) -> usize {
+        self.inner.vocab_len()
+    }
+}
+
+/// One of the results of `Segmenter.segmentNBest()`
+#[wasm_bindgen]
+pub struct Segmentation {
+    words: Vec<String>,
+    score: f64,
+}
+
+#[wasm_bindgen]
+impl Segmentation {
+    /// The words found in the segmented text
+    #[wasm_bindgen(getter)]
+    pub fn words(&self) -> Vec<String> {
+        self.words.clone()
+    }
+
+    /// The score of the segmentation
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+/// Lowercase `input` and remove all characters that can't be segmented
+#[wasm_bindgen]
+pub fn clean(input: &str) -> String {
+    instant_segment::clean(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instant_segment::model::Metadata;
+
+    #[test]
+    fn test_segmenter() {
+        let segmenter = instant_segment::test_cases::choose_spain();
+        let mut model = Vec::new();
+        segmenter
+            .save_compact(&mut model, &Metadata::default())
+            .unwrap();
+
+        let mut segmenter = Segmenter::new(&model).ok().unwrap();
+        assert_eq!(segmenter.vocab_len(), 4);
+        let words = segmenter.segment(&clean("Choose Spain!")).ok().unwrap();
+        assert_eq!(words, ["choose", "spain"]);
+
+        let results = segmenter.segment_n_best("choosespain", 2).ok().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].words(), ["choose", "spain"]);
+        assert!(results[0].score() >= results[1].score());
+
+        let words = vec!["choose".to_owned(), "spain".to_owned()];
+        let score = segmenter.score_sentence(words).unwrap();
+        assert!((score - results[0].score()).abs() < 1e-9);
+        assert_eq!(segmenter.score_sentence(Vec::new()), None);
+    }
+}
@@ -3,9 +3,9 @@
 use bencher::{benchmark_group, benchmark_main, Bencher};
 
 use instant_segment::test_data::{crate_data_dir, segmenter};
-use instant_segment::Search;
+use instant_segment::{Beam, Search};
 
-benchmark_group!(benches, short, long, extra_long);
+benchmark_group!(benches, short, long, extra_long, extra_long_beam);
 benchmark_main!(benches);
 
 fn short(bench: &mut Bencher) {
@@ -37,3 +37,16 @@ fn extra_long(bench: &mut Bencher) {
         );
     });
 }
+
+fn extra_long_beam(bench: &mut Bencher) {
+    let segmenter = segmenter(crate_data_dir());
+    let mut search = Search::default();
+    let beam = Beam::default();
+    bench.iter(|| {
+        let _ = segmenter.segment_beam(
+"itwasthebestoftimesitwastheworstoftimesitwastheageofwisdomitwastheageoffoolishnessitwastheepochofbeliefitwastheepochofincredulityitwastheseasonoflightitwastheseasonofdarknessitwasthespringofhopeitwasthewinterofdespair",
+            &mut search,
+            &beam,
+        );
+    });
+}
@@ -0,0 +1,411 @@
+//! Command-line tools for working with segmentation models
+//!
+//! This is not actually an example, but a collection of tools for evaluating models. Run it
+//! with `cargo run --release --example cli -- <command>`; see `usage()` for the commands.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::exit;
+
+use instant_segment::eval::{self, Report};
+use instant_segment::model::Metadata;
+use instant_segment::ngrams::{self, Import};
+use instant_segment::prune::Pruning;
+use instant_segment::tune::Grid;
+use instant_segment::{Beam, ModelError, Search, Segmenter};
+use smartstring::alias::String as SmartString;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("eval") => eval(args),
+        Some("tune") => tune(args),
+        Some("prune") => prune(args),
+        Some("import") => import(args),
+        _ => Err(usage()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{e}");
+        exit(1);
+    }
+}
+
+/// Evaluate one or more configurations against a gold file, side by side
+fn eval(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut gold = None;
+    let mut configs = Vec::<Config>::new();
+    let mut diffs = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => configs.push(Config::load(&value(&mut args, &arg)?, None)?),
+            "--counts" => {
+                let unigrams = value(&mut args, &arg)?;
+                let bigrams = value(&mut args, &arg)?;
+                configs.push(Config::load(&unigrams, Some(&bigrams))?);
+            }
+            "--limit" | "--beam" => {
+                let n = value(&mut args, &arg)?
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid value for {arg}: {e}"))?;
+                if n == 0 {
+                    return Err(format!("{arg} must be positive"));
+                }
+                let config = configs
+                    .last_mut()
+                    .ok_or_else(|| format!("{arg} must follow --model or --counts"))?;
+                match arg.as_str() {
+                    "--limit" => config.segmenter.set_limit(n),
+                    _ => {
+                        config.beam = Some(Beam::new(n, f64::INFINITY).map_err(|e| e.to_string())?)
+                    }
+                }
+                config.name = format!("{} {arg} {n}", config.name);
+            }
+            "--diffs" => diffs = true,
+            _ if gold.is_none() && !arg.starts_with("--") => gold = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+
+    let gold = read_gold(&gold.ok_or_else(usage)?)?;
+    if configs.is_empty() {
+        return Err(usage());
+    }
+
+    let reports = configs
+        .iter()
+        .map(|config| (config.name.as_str(), config.evaluate(&gold)))
+        .collect::<Vec<_>>();
+
+    for (name, report) in &reports {
+        println!("{name}:\n{report}\n");
+        if diffs {
+            for diff in &report.diffs {
+                println!("{diff}\n");
+            }
+        }
+    }
+
+    if reports.len() > 1 {
+        let reports = reports
+            .iter()
+            .map(|(name, report)| (*name, report))
+            .collect::<Vec<_>>();
+        print!("{}", eval::side_by_side(&reports));
+    }
+
+    Ok(())
+}
+
+/// Grid search the word length limit and scoring parameters, optionally saving the best
+fn tune(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut gold = None;
+    let mut config = None;
+    let mut grid = Grid::default();
+    let mut output = None;
+    let mut top = 10;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => config = Some(Config::load(&value(&mut args, &arg)?, None)?),
+            "--counts" => {
+                let unigrams = value(&mut args, &arg)?;
+                let bigrams = value(&mut args, &arg)?;
+                config = Some(Config::load(&unigrams, Some(&bigrams))?);
+            }
+            "--limits" => grid.limits = list(&value(&mut args, &arg)?, &arg)?,
+            "--unknown-base" => grid.unknown_base = list(&value(&mut args, &arg)?, &arg)?,
+            "--unknown-word-len" => grid.unknown_word_len = list(&value(&mut args, &arg)?, &arg)?,
+            "--bigram-weight" => grid.bigram_weight = list(&value(&mut args, &arg)?, &arg)?,
+            "--top" => top = parse(&mut args, &arg)?,
+            "--output" => output = Some(value(&mut args, &arg)?),
+            _ if gold.is_none() && !arg.starts_with("--") => gold = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+
+    let gold = read_gold(&gold.ok_or_else(usage)?)?;
+    let mut config = config.ok_or_else(usage)?;
+    let trials = config
+        .segmenter
+        .tune(&gold, &grid)
+        .map_err(|e| e.to_string())?;
+    let best = trials.first().ok_or("empty parameter grid")?;
+
+    println!(
+        "{:>5} {:>12} {:>16} {:>13} {:>11} {:>8}",
+        "limit", "unknown base", "unknown word len", "bigram weight", "exact match", "word F1"
+    );
+    for trial in trials.iter().take(top) {
+        println!(
+            "{:>5} {:>12} {:>16} {:>13} {:>11.4} {:>8.4}",
+            trial.limit,
+            trial.params.unknown_base,
+            trial.params.unknown_word_len,
+            trial.params.bigram_weight,
+            trial.report.accuracy(),
+            trial.report.words.f1()
+        );
+    }
+    println!("\nbest configuration:\n{}", best.report);
+
+    match output {
+        Some(path) => config.save(&path, false),
+        None => Ok(()),
+    }
+}
+
+/// Prune a model to a smaller vocabulary or memory budget
+fn prune(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut config = None;
+    let mut pruning = Pruning::default();
+    let mut held_out = None;
+    let mut output = None;
+    let mut compact = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => config = Some(Config::load(&value(&mut args, &arg)?, None)?),
+            "--counts" => {
+                let unigrams = value(&mut args, &arg)?;
+                let bigrams = value(&mut args, &arg)?;
+                config = Some(Config::load(&unigrams, Some(&bigrams))?);
+            }
+            "--min-unigram-freq" => pruning.min_unigram_freq = parse(&mut args, &arg)?,
+            "--max-vocabulary" => pruning.max_vocabulary = Some(parse(&mut args, &arg)?),
+            "--min-bigram-freq" => pruning.min_bigram_freq = parse(&mut args, &arg)?,
+            "--min-entropy" => pruning.min_entropy = parse(&mut args, &arg)?,
+            "--max-memory" => pruning.max_memory = Some(parse(&mut args, &arg)?),
+            "--held-out" => held_out = Some(read_gold(&value(&mut args, &arg)?)?),
+            "--output" => output = Some(value(&mut args, &arg)?),
+            "--compact" => compact = true,
+            _ => return Err(usage()),
+        }
+    }
+
+    let mut config = config.ok_or_else(usage)?;
+    let output = output.ok_or_else(usage)?;
+    let report = config
+        .segmenter
+        .prune(&pruning, held_out.as_deref().unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    println!("{report}");
+    config.save(&output, compact)
+}
+
+/// Import word counts from the Google Books Ngram data
+fn import(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut input = None;
+    let mut state = None;
+    let mut import = Import::default();
+    let mut text = None;
+    let mut model = None;
+    let mut compact = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--state" => state = Some(value(&mut args, &arg)?),
+            "--language" => import.language = value(&mut args, &arg)?,
+            "--years" => {
+                let years = value(&mut args, &arg)?;
+                let (start, end) = years.split_once('-').unwrap_or((&years, &years));
+                let year = |year: &str, default| match year {
+                    "" => Ok(default),
+                    _ => year
+                        .parse()
+                        .map_err(|e| format!("invalid value for {arg}: {e}")),
+                };
+                import.start_year = year(start, 0)?;
+                import.end_year = year(end, u16::MAX)?;
+            }
+            "--word-list" => {
+                let path = value(&mut args, &arg)?;
+                let word_list = File::open(&path)
+                    .map_err(ModelError::from)
+                    .and_then(|f| ngrams::read_word_list(BufReader::new(f)))
+                    .map_err(|e| format!("error reading {path}: {e}"))?;
+                import.word_list = Some(word_list);
+            }
+            "--max-unigrams" => import.max_unigrams = Some(parse(&mut args, &arg)?),
+            "--max-bigrams" => import.max_bigrams = Some(parse(&mut args, &arg)?),
+            "--text" => {
+                let unigrams = value(&mut args, &arg)?;
+                text = Some((unigrams, value(&mut args, &arg)?));
+            }
+            "--model" => model = Some(value(&mut args, &arg)?),
+            "--compact" => compact = true,
+            _ if input.is_none() && !arg.starts_with("--") => input = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+
+    let input = input.ok_or_else(usage)?;
+    let state = state.ok_or_else(usage)?;
+    if text.is_none() && model.is_none() {
+        return Err(usage());
+    }
+
+    let ngrams = import
+        .run(Path::new(&input), Path::new(&state), |part| {
+            match part.ngrams {
+                Some(n) => eprintln!("extracted {n} ngrams from {}", part.path.display()),
+                None => eprintln!("using saved counts for {}", part.path.display()),
+            };
+        })
+        .map_err(|e| format!("error importing {input}: {e}"))?;
+    eprintln!(
+        "imported {} unigrams and {} bigrams",
+        ngrams.unigrams.len(),
+        ngrams.bigrams.len()
+    );
+
+    if let Some((unigrams, bigrams)) = &text {
+        let create = |path: &str| File::create(path).map(BufWriter::new);
+        create(unigrams)
+            .and_then(|u| Ok((u, create(bigrams)?)))
+            .map_err(ModelError::from)
+            .and_then(|(u, b)| ngrams.write(u, b))
+            .map_err(|e| format!("error writing {unigrams} and {bigrams}: {e}"))?;
+    }
+
+    if let Some(path) = model {
+        let source = format!(
+            "Google Books Ngram data ({}, {}-{})",
+            import.language, import.start_year, import.end_year
+        );
+        let config = Config {
+            name: source,
+            segmenter: ngrams.into_segmenter(),
+            beam: None,
+        };
+        config.save(&path, compact)?;
+    }
+
+    Ok(())
+}
+
+/// A model and the settings to segment with
+struct Config {
+    name: String,
+    segmenter: Segmenter,
+    beam: Option<Beam>,
+}
+
+impl Config {
+    /// Load a binary model from `path`, or word counts if `bigrams` is given
+    fn load(path: &str, bigrams: Option<&str>) -> Result<Self, String> {
+        let segmenter = match bigrams {
+            Some(bigrams) => Segmenter::from_files(path, bigrams),
+            None => File::open(path)
+                .map_err(ModelError::from)
+                .and_then(|f| Segmenter::load(BufReader::new(f))),
+        }
+        .map_err(|e| format!("error loading {path}: {e}"))?;
+
+        let name = Path::new(path)
+            .file_name()
+            .map_or_else(|| path.to_owned(), |name| name.to_string_lossy().into());
+        Ok(Self {
+            name,
+            segmenter,
+            beam: None,
+        })
+    }
+
+    /// Save the model in the binary format to `path`, optionally in the compact encoding
+    fn save(&self, path: &str, compact: bool) -> Result<(), String> {
+        let metadata = Metadata {
+            source: self.name.clone(),
+            ..Metadata::default()
+        };
+        File::create(path)
+            .map_err(ModelError::from)
+            .and_then(|f| {
+                let mut writer = BufWriter::new(f);
+                match compact {
+                    true => self.segmenter.save_compact(&mut writer, &metadata)?,
+                    false => self.segmenter.save(&mut writer, &metadata)?,
+                }
+                Ok(writer.flush()?)
+            })
+            .map_err(|e| format!("error writing {path}: {e}"))
+    }
+
+    fn evaluate(&self, gold: &[Vec<SmartString>]) -> Report {
+        let beam = match &self.beam {
+            Some(beam) => beam,
+            None => return self.segmenter.evaluate(gold),
+        };
+
+        let mut search = Search::default();
+        eval::evaluate(gold, |input| {
+            let words = self.segmenter.segment_beam(input, &mut search, beam)?;
+            Ok(words.map(SmartString::from).collect())
+        })
+    }
+}
+
+/// Read gold segmentations from `path`, or from stdin if it's `-`
+fn read_gold(path: &str) -> Result<Vec<Vec<SmartString>>, String> {
+    match path {
+        "-" => eval::read_gold(io::stdin().lock()),
+        path => File::open(path)
+            .map_err(ModelError::from)
+            .and_then(|f| eval::read_gold(BufReader::new(f))),
+    }
+    .map_err(|e| format!("error reading {path}: {e}"))
+}
+
+/// Parse a comma-separated list of values for `option`
+fn list<T: std::str::FromStr>(value: &str, option: &str) -> Result<Vec<T>, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .split(',')
+        .map(|v| v.trim().parse::<T>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid value for {option}: {e}"))
+}
+
+/// Parse the value for `option`
+fn parse<T: std::str::FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    option: &str,
+) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value(args, option)?
+        .parse()
+        .map_err(|e| format!("invalid value for {option}: {e}"))
+}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {option}"))
+}
+
+fn usage() -> String {
+    "usage: cli eval [--diffs] GOLD (--model PATH | --counts UNIGRAMS BIGRAMS) \
+     [--limit N] [--beam WIDTH]...\n\n\
+     Evaluates each model against the GOLD file (`-` for stdin), which has one example per \
+     line with the words separated by spaces. Options following a model apply to it; repeat \
+     --model or --counts to compare configurations side by side.\n\n\
+     usage: cli tune GOLD (--model PATH | --counts UNIGRAMS BIGRAMS) [--limits N,...] \
+     [--unknown-base X,...] [--unknown-word-len X,...] [--bigram-weight X,...] [--top N] \
+     [--output PATH]\n\n\
+     Evaluates every combination of the given parameter values against the GOLD file and \
+     prints the best ones. With --output, saves the model with the best configuration.\n\n\
+     usage: cli prune (--model PATH | --counts UNIGRAMS BIGRAMS) [--min-unigram-freq X] \
+     [--max-vocabulary N] [--min-bigram-freq X] [--min-entropy X] [--max-memory BYTES] \
+     [--held-out GOLD] --output PATH [--compact]\n\n\
+     Prunes the model and saves it to PATH, reporting the change in score per word on the \
+     GOLD file if given. With --compact, writes the smaller compact encoding.\n\n\
+     usage: cli import INPUT --state DIR [--language LANG] [--years START-END] \
+     [--word-list PATH] [--max-unigrams N] [--max-bigrams N] \
+     (--text UNIGRAMS BIGRAMS | --model PATH [--compact])...\n\n\
+     Imports the Google Books Ngram files in the INPUT directory, saving the counts for each \
+     file in the state DIR so that the import can be resumed. Writes word count files, a \
+     binary model, or both."
+        .to_owned()
+}
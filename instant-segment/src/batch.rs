@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{InvalidCharacter, Search, Segmentation, Segmenter};
+
+impl Segmenter {
+    /// Segment all of the given `inputs` in parallel
+    ///
+    /// Segmentation happens on the global rayon thread pool (or the pool this is called from).
+    /// Each worker thread keeps its own [`Search`], so that allocations are reused across inputs.
+    /// Results are returned in the same order as the `inputs`.
+    pub fn segment_batch(&self, inputs: &[&str]) -> Vec<Result<Segmentation, InvalidCharacter>> {
+        inputs
+            .par_iter()
+            .map(|input| {
+                SEARCH.with(|search| {
+                    let mut search = search.borrow_mut();
+                    self.segment(input, &mut search).map(Segmentation::from)
+                })
+            })
+            .collect()
+    }
+}
+
+thread_local! {
+    static SEARCH: RefCell<Search> = RefCell::new(Search::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{choose_spain, segment};
+
+    #[test]
+    fn test_segment_batch() {
+        let segmenter = choose_spain();
+        let results = segmenter.segment_batch(&["choosespain", "", "Spain", "pain"]);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().words(), ["choose", "spain"]);
+        assert!(results[1].as_ref().unwrap().words().is_empty());
+        assert!(results[2].is_err());
+        assert_eq!(results[3].as_ref().unwrap().words(), ["pain"]);
+
+        assert!(segmenter.segment_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_segment_batch_order() {
+        // More inputs than threads, so that they are split across them
+        let segmenter = choose_spain();
+        let inputs = (0..1000)
+            .map(|i| "choosespain".repeat(i % 7))
+            .collect::<Vec<_>>();
+        let inputs = inputs.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        let results = segmenter.segment_batch(&inputs);
+        for (input, result) in inputs.iter().zip(results) {
+            assert_eq!(result.unwrap().words(), segment(&segmenter, input));
+        }
+    }
+}
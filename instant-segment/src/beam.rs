@@ -0,0 +1,187 @@
+use std::{fmt, mem};
+
+use crate::{Ascii, InvalidCharacter, Search, SegmentState, Segmenter, Segments};
+
+impl Segmenter {
+    /// Segment the text in `input` using beam search
+    ///
+    /// Like [`Segmenter::segment()`], but for each end position only the most promising start
+    /// positions (as configured by the [`Beam`]) are considered. This trades exactness for speed
+    /// on long inputs. Results are deterministic; [`Segments::pruned()`] indicates whether any
+    /// start positions were skipped, in which case the result may differ from the exact one.
+    pub fn segment_beam<'a>(
+        &self,
+        input: &str,
+        search: &'a mut Search,
+        beam: &Beam,
+    ) -> Result<Segments<'a>, InvalidCharacter> {
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
+        let (score, pruned) = match input {
+            "" => (0.0, false),
+            _ => {
+                let pruned = state.run_beam(beam);
+                (state.backtrack(), pruned)
+            }
+        };
+
+        Ok(Segments {
+            iter: search.result.iter(),
+            score,
+            pruned,
+        })
+    }
+}
+
+impl SegmentState<'_> {
+    /// Fill the candidates, only starting words at positions inside the beam
+    ///
+    /// The beam holds at most `width` start positions within the word length limit. Positions
+    /// are compared by their average score per byte, since longer prefixes naturally have lower
+    /// scores. Returns whether any positions were pruned.
+    fn run_beam(&mut self, beam: &Beam) -> bool {
+        let mut pruned = false;
+        let mut starts = mem::take(&mut self.search.beam);
+        starts.clear();
+        starts.push((0.0, 0));
+        for end in 1..=self.text.len() {
+            let start = end.saturating_sub(self.data.limit());
+            starts.retain(|&(_, split)| split >= start);
+            for &(_, split) in &starts {
+                self.update(split, end);
+            }
+
+            // Make sure there's always a candidate, even if the whole beam expired
+            if starts.is_empty() {
+                self.update(end - 1, end);
+            }
+
+            let rate = self.search.candidates[end - 1].score / end as f64;
+            let best = starts
+                .iter()
+                .map(|&(rate, _)| rate)
+                .fold(f64::NEG_INFINITY, f64::max);
+
+            if rate < best - beam.margin {
+                pruned = true;
+                continue;
+            }
+
+            starts.push((rate, end));
+            if starts.len() > beam.width {
+                // Evict the worst position, preferring to keep later positions on ties
+                let (worst, _) = starts
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)))
+                    .unwrap();
+                starts.remove(worst);
+                pruned = true;
+            }
+        }
+
+        self.search.beam = starts;
+        pruned
+    }
+}
+
+/// Configuration for [`Segmenter::segment_beam()`]
+#[derive(Clone, Copy, Debug)]
+pub struct Beam {
+    width: usize,
+    margin: f64,
+}
+
+impl Beam {
+    /// Create a beam configuration
+    ///
+    /// At most `width` positions within the word length limit are used to start words, evicting
+    /// the position with the lowest average score per byte when the beam is full. Positions for
+    /// which the average score per byte is more than `margin` below the best position in the
+    /// beam are never used to start words.
+    ///
+    /// Returns an error if `width` is zero or `margin` is negative or NaN. Use
+    /// [`f64::INFINITY`] as `margin` to only limit the beam by its width.
+    pub fn new(width: usize, margin: f64) -> Result<Self, InvalidBeam> {
+        if width == 0 {
+            Err(InvalidBeam("width must be positive"))
+        } else if margin.is_nan() || margin < 0.0 {
+            Err(InvalidBeam("margin must be non-negative"))
+        } else {
+            Ok(Self { width, margin })
+        }
+    }
+}
+
+impl Default for Beam {
+    fn default() -> Self {
+        Self {
+            width: 12,
+            margin: 2.0,
+        }
+    }
+}
+
+/// Error returned for a [`Beam`] configuration that is out of range
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidBeam(&'static str);
+
+impl std::error::Error for InvalidBeam {}
+
+impl fmt::Display for InvalidBeam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid beam configuration: {}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    #[test]
+    fn test_beam() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let exact = segmenter.segment("choosespain", &mut search).unwrap();
+        assert!(!exact.pruned());
+        let score = exact.score();
+
+        let segments = segmenter
+            .segment_beam("choosespain", &mut search, &Beam::new(3, 1.0).unwrap())
+            .unwrap();
+        assert!(segments.pruned());
+        assert!((segments.score() - score).abs() < 1e-9);
+        assert_eq!(segments.collect::<Vec<_>>(), ["choose", "spain"]);
+
+        let wide = Beam::new(usize::MAX, f64::INFINITY).unwrap();
+        let segments = segmenter
+            .segment_beam("choosespain", &mut search, &wide)
+            .unwrap();
+        assert!(!segments.pruned());
+        assert!((segments.score() - score).abs() < 1e-9);
+
+        // The narrowest beams still segment all of the input
+        for beam in [Beam::new(1, f64::INFINITY), Beam::new(1, 0.0)] {
+            let beam = beam.unwrap();
+            let segments = segmenter
+                .segment_beam("choosespain", &mut search, &beam)
+                .unwrap();
+            assert!(segments.pruned());
+            assert!(segments.score() <= score);
+            assert_eq!(segments.collect::<String>(), "choosespain");
+        }
+
+        let empty = segmenter
+            .segment_beam("", &mut search, &Beam::new(1, 0.0).unwrap())
+            .unwrap();
+        assert!(!empty.pruned());
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_invalid_beam() {
+        assert!(Beam::new(0, 1.0).is_err());
+        assert!(Beam::new(1, -1.0).is_err());
+        assert!(Beam::new(1, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Compressed word count files and models
+//!
+//! Gzip and zstd compression are supported through the `gzip` and `zstd` features. Readers in
+//! this crate detect compressed input automatically; use an [`Encoder`] to write it.
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::ModelError;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression formats for writing data
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// No compression
+    None,
+    /// Gzip compression
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstd compression
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Wrap `reader` to decompress its contents if they are compressed
+///
+/// The compression format is detected from the first bytes of the input. Returns
+/// [`ModelError::UnsupportedCompression`] if the input is compressed in a format for which the
+/// feature is not enabled.
+pub fn decode<'a>(mut reader: impl BufRead + 'a) -> Result<Box<dyn BufRead + 'a>, ModelError> {
+    // A single `fill_buf()` may return fewer bytes than the magic numbers, so read them in full
+    let mut start = Vec::with_capacity(ZSTD_MAGIC.len());
+    (&mut reader)
+        .take(ZSTD_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    let (gzip, zstd) = (start.starts_with(GZIP_MAGIC), start.starts_with(ZSTD_MAGIC));
+    let reader = io::Cursor::new(start).chain(reader);
+
+    if gzip {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(io::BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )));
+        #[cfg(not(feature = "gzip"))]
+        return Err(ModelError::UnsupportedCompression("gzip"));
+    }
+
+    if zstd {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(io::BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(ModelError::UnsupportedCompression("zstd"));
+    }
+
+    Ok(Box::new(reader))
+}
+
+/// Writer that compresses the data written to it
+///
+/// Call [`Encoder::finish()`] after writing all data to make sure the output is complete.
+pub struct Encoder<W: Write> {
+    inner: Inner<W>,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder writing data compressed with `compression` to `writer`
+    pub fn new(writer: W, compression: Compression) -> io::Result<Self> {
+        let inner = match compression {
+            Compression::None => Inner::None(writer),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Inner::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Inner::Zstd(zstd::Encoder::new(writer, 0)?),
+        };
+
+        Ok(Self { inner })
+    }
+
+    /// Write any remaining compressed data, returning the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        // Without compression features enabled, there is only a single variant
+        #[allow(clippy::infallible_destructuring_match)]
+        let mut writer = match self.inner {
+            Inner::None(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(encoder) => encoder.finish()?,
+        };
+
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::None(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::None(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+enum Inner<W: Write> {
+    None(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counts::{read_unigrams, write_unigrams};
+    use crate::model::Metadata;
+    use crate::Segmenter;
+
+    #[test]
+    fn test_compression() {
+        let unigrams = [("choose", 80_000.0), ("spain", 20_000.0)];
+        let segmenter = Segmenter::new(
+            unigrams.iter().map(|&(word, count)| (word.into(), count)),
+            [],
+        );
+
+        let formats = [
+            Compression::None,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ];
+
+        for compression in formats {
+            let mut encoder = Encoder::new(Vec::new(), compression).unwrap();
+            write_unigrams(&mut encoder, unigrams.iter().copied()).unwrap();
+            let buf = encoder.finish().unwrap();
+            assert_eq!(buf.starts_with(b"choose"), compression == Compression::None);
+            assert_eq!(read_unigrams(&buf[..]).unwrap().len(), 2);
+            // Input that arrives one byte at a time is still detected
+            let one_byte = io::BufReader::with_capacity(1, &buf[..]);
+            assert_eq!(read_unigrams(one_byte).unwrap().len(), 2);
+
+            let mut encoder = Encoder::new(Vec::new(), compression).unwrap();
+            segmenter.save(&mut encoder, &Metadata::default()).unwrap();
+            let buf = encoder.finish().unwrap();
+            let (_, info) = Segmenter::load_with_info(&buf[..]).unwrap();
+            assert_eq!(info.vocabulary_size, 2);
+        }
+
+        #[cfg(not(feature = "gzip"))]
+        assert!(matches!(
+            read_unigrams(&[0x1f, 0x8b, 0x08, 0x00][..]),
+            Err(ModelError::UnsupportedCompression("gzip"))
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+use std::f64::consts::LN_10;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Ascii, InvalidCharacter, Search, SegmentState, Segmentation, Segmenter, Segments};
+
+impl Segmenter {
+    /// Segment the text in `input`, returning an owned result with its [`Confidence`]
+    ///
+    /// In addition to the best segmentation, this sums the probabilities over all segmentations
+    /// considered by the search (using the forward algorithm), such that the probability of the
+    /// best segmentation can be expressed relative to all alternatives.
+    pub fn segment_with_confidence(
+        &self,
+        input: &str,
+        search: &mut Search,
+    ) -> Result<Segmentation, InvalidCharacter> {
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
+        let (score, confidence) = match input {
+            "" => (0.0, Confidence::default()),
+            _ => {
+                let score = state.run();
+                let total = state.forward_backward();
+                (score, state.confidence(score, total))
+            }
+        };
+
+        let mut segmentation = Segmentation::from(Segments {
+            iter: search.result.iter(),
+            score,
+            pruned: false,
+        });
+        segmentation.confidence = Some(confidence);
+        Ok(segmentation)
+    }
+
+    /// Marginal probability of a word boundary at every byte position in `input`
+    ///
+    /// Returns `input.len() + 1` probabilities, where the item at index `i` is the probability
+    /// (summed over all segmentations considered by the search) that a word starts or ends at
+    /// byte offset `i`. The first and last items are always `1.0`. Positions with intermediate
+    /// values are those where the model is uncertain about splitting.
+    pub fn boundary_probabilities(
+        &self,
+        input: &str,
+        search: &mut Search,
+    ) -> Result<Vec<f64>, InvalidCharacter> {
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
+        if input.is_empty() {
+            return Ok(vec![1.0]);
+        }
+
+        state.run();
+        let total = state.forward_backward();
+        Ok((0..=input.len())
+            .map(|idx| state.boundary_probability(idx, total))
+            .collect())
+    }
+}
+
+impl SegmentState<'_> {
+    /// Run the forward and backward passes, returning the total log probability
+    ///
+    /// Must be called after `run()`, which determines the previous word used for the bigram
+    /// scores of the words starting at each position. This means the forward pass sums over
+    /// exactly the word lattice explored by `run()`.
+    fn forward_backward(&mut self) -> f64 {
+        let len = self.text.len();
+        let limit = self.data.limit().min(len);
+        self.search.edges.clear();
+        self.search.edges.resize(len * limit, f64::NEG_INFINITY);
+
+        self.search.forward.clear();
+        self.search.forward.push(0.0);
+        for end in 1..=len {
+            let mut total = f64::NEG_INFINITY;
+            for split in end.saturating_sub(limit)..end {
+                let score = self.edge_score(split, end);
+                self.search.edges[(end - 1) * limit + (end - 1 - split)] = score;
+                total = log10_add(total, self.search.forward[split] + score);
+            }
+            self.search.forward.push(total);
+        }
+
+        self.search.backward.clear();
+        self.search.backward.resize(len + 1, f64::NEG_INFINITY);
+        self.search.backward[len] = 0.0;
+        for split in (0..len).rev() {
+            let mut total = f64::NEG_INFINITY;
+            for end in split + 1..=(split + limit).min(len) {
+                let score = self.search.edges[(end - 1) * limit + (end - 1 - split)];
+                total = log10_add(total, score + self.search.backward[end]);
+            }
+            self.search.backward[split] = total;
+        }
+
+        self.search.forward[len]
+    }
+
+    /// Probability that a word boundary falls at byte position `idx`
+    ///
+    /// Only valid after calling `forward_backward()`.
+    fn boundary_probability(&self, idx: usize, total: f64) -> f64 {
+        probability(self.search.forward[idx] + self.search.backward[idx] - total)
+    }
+
+    fn confidence(&self, score: f64, total: f64) -> Confidence {
+        let mut end = 0;
+        let mut boundaries = Vec::with_capacity(self.search.result.len().saturating_sub(1));
+        for word in &self.search.result[..self.search.result.len() - 1] {
+            end += word.len();
+            boundaries.push(self.boundary_probability(end, total));
+        }
+
+        Confidence {
+            posterior: probability(score - total),
+            log10_total: total,
+            boundaries,
+        }
+    }
+}
+
+/// Confidence in a [`Segmentation`], relative to the alternative segmentations
+#[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Confidence {
+    /// Probability of this segmentation relative to all segmentations considered
+    pub posterior: f64,
+    /// Base-10 logarithm of the summed score of all segmentations considered
+    pub log10_total: f64,
+    /// Marginal probability of each of the word boundaries in the segmentation
+    ///
+    /// Contains one item fewer than the number of words: the probability that any segmentation
+    /// has a word boundary at the end of the word at the same index.
+    pub boundaries: Vec<f64>,
+}
+
+impl Default for Confidence {
+    fn default() -> Self {
+        Self {
+            posterior: 1.0,
+            log10_total: 0.0,
+            boundaries: Vec::new(),
+        }
+    }
+}
+
+/// Convert a base-10 log probability relative to the total, clamping rounding errors above `1.0`
+fn probability(log10: f64) -> f64 {
+    10f64.powf(log10).min(1.0)
+}
+
+/// Calculate `log10(10^a + 10^b)` without leaving the log domain
+fn log10_add(a: f64, b: f64) -> f64 {
+    let (hi, lo) = match a > b {
+        true => (a, b),
+        false => (b, a),
+    };
+
+    if lo == f64::NEG_INFINITY {
+        return hi;
+    }
+
+    hi + (10f64.powf(lo - hi)).ln_1p() / LN_10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    #[test]
+    fn test_log10_add() {
+        assert!((log10_add(1.0, 1.0) - 2f64.log10() - 1.0).abs() < 1e-12);
+        assert_eq!(log10_add(f64::NEG_INFINITY, -3.0), -3.0);
+        assert_eq!(
+            log10_add(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn test_probability() {
+        assert_eq!(probability(0.0), 1.0);
+        assert_eq!(probability(1e-15), 1.0);
+        assert!((probability(-1.0) - 0.1).abs() < 1e-12);
+        assert_eq!(probability(f64::NEG_INFINITY), 0.0);
+    }
+
+    #[test]
+    fn test_confidence() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let result = segmenter
+            .segment_with_confidence("choosespain", &mut search)
+            .unwrap();
+        assert_eq!(result.words(), ["choose", "spain"]);
+
+        let confidence = result.confidence().unwrap();
+        assert!(confidence.posterior > 0.5 && confidence.posterior < 1.0);
+        assert!(confidence.log10_total >= result.score());
+        assert_eq!(confidence.boundaries.len(), 1);
+        assert!(confidence.boundaries[0] >= confidence.posterior);
+        assert!(confidence.boundaries[0] <= 1.0);
+
+        // Without alternatives, the segmentation is certain
+        let empty = segmenter.segment_with_confidence("", &mut search).unwrap();
+        assert_eq!(empty.confidence(), Some(&Confidence::default()));
+        let single = segmenter.segment_with_confidence("x", &mut search).unwrap();
+        let confidence = single.confidence().unwrap();
+        assert_eq!(confidence.posterior, 1.0);
+        assert!(confidence.boundaries.is_empty());
+
+        assert!(segmenter
+            .segment_with_confidence("Spain", &mut search)
+            .is_err());
+
+        // Limits beyond the input length must not affect the result
+        let mut unlimited = choose_spain();
+        unlimited.set_limit(usize::MAX);
+        let unlimited = unlimited
+            .segment_with_confidence("choosespain", &mut search)
+            .unwrap();
+        assert_eq!(unlimited, result);
+    }
+
+    #[test]
+    fn test_boundary_probabilities() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let boundaries = segmenter
+            .boundary_probabilities("choosespain", &mut search)
+            .unwrap();
+        assert_eq!(boundaries.len(), 12);
+        assert!((boundaries[0] - 1.0).abs() < 1e-9);
+        assert!((boundaries[11] - 1.0).abs() < 1e-9);
+        assert!(boundaries[6] > boundaries[7]);
+        assert!(boundaries.iter().all(|&p| (0.0..=1.0).contains(&p)));
+
+        // Input without a choice of splits only has the boundaries at either end
+        let empty = segmenter.boundary_probabilities("", &mut search).unwrap();
+        assert_eq!(empty, [1.0]);
+        let single = segmenter.boundary_probabilities("x", &mut search).unwrap();
+        assert_eq!(single, [1.0, 1.0]);
+        assert!(segmenter
+            .boundary_probabilities("x y", &mut search)
+            .is_err());
+
+        let best = segmenter
+            .segment_with_confidence("choosespain", &mut search)
+            .unwrap();
+        assert!((boundaries[6] - best.confidence().unwrap().boundaries[0]).abs() < 1e-9);
+
+        // The backward pass must not overflow on limits beyond the input length
+        let mut unlimited = choose_spain();
+        unlimited.set_limit(usize::MAX);
+        let unlimited = unlimited
+            .boundary_probabilities("choosespain", &mut search)
+            .unwrap();
+        assert_eq!(unlimited, boundaries);
+    }
+}
@@ -0,0 +1,150 @@
+//! Reading and writing word count files
+//!
+//! Unigram files contain one word per line, followed by a tab and the word's count. Bigram files
+//! contain two words separated by a space, followed by a tab and the count. These are the
+//! formats used by the data files shipped with this crate (see `data/README.md`). Compressed
+//! files are detected automatically (see [`crate::compression`]).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use smartstring::alias::String;
+
+use crate::compression::decode;
+use crate::{ModelError, Segmenter};
+
+impl Segmenter {
+    /// Create `Segmenter` from unigram and bigram count files
+    pub fn from_files(
+        unigrams: impl AsRef<Path>,
+        bigrams: impl AsRef<Path>,
+    ) -> Result<Self, ModelError> {
+        let (unigrams, bigrams) = (unigrams.as_ref(), bigrams.as_ref());
+        let unigrams = read_unigrams(open(unigrams)?).map_err(|e| e.in_file(unigrams))?;
+        let bigrams = read_bigrams(open(bigrams)?).map_err(|e| e.in_file(bigrams))?;
+        Ok(Self::new(unigrams, bigrams))
+    }
+}
+
+type Bigrams = Vec<((String, String), f64)>;
+
+pub(crate) fn open(path: &Path) -> Result<BufReader<File>, ModelError> {
+    Ok(BufReader::with_capacity(4 * 1024 * 1024, File::open(path)?))
+}
+
+/// Read unigram counts from `reader`
+///
+/// Empty lines are skipped.
+pub fn read_unigrams(reader: impl BufRead) -> Result<Vec<(String, f64)>, ModelError> {
+    let mut unigrams = Vec::new();
+    for_each_line(reader, |ln| {
+        let (word, count) = split_count(ln)?;
+        unigrams.push((word.into(), count));
+        Ok(())
+    })?;
+    Ok(unigrams)
+}
+
+/// Read bigram counts from `reader`
+///
+/// Empty lines are skipped.
+pub fn read_bigrams(reader: impl BufRead) -> Result<Bigrams, ModelError> {
+    let mut bigrams = Vec::new();
+    for_each_line(reader, |ln| {
+        let (words, count) = split_count(ln)?;
+        let (first, second) = words.split_once(' ').ok_or("no space found")?;
+        bigrams.push(((first.into(), second.into()), count));
+        Ok(())
+    })?;
+    Ok(bigrams)
+}
+
+/// Write unigram counts to `writer`, in the format expected by [`read_unigrams()`]
+pub fn write_unigrams<'a>(
+    mut writer: impl Write,
+    unigrams: impl IntoIterator<Item = (&'a str, f64)>,
+) -> Result<(), ModelError> {
+    for (word, count) in unigrams {
+        writeln!(writer, "{word}\t{count}")?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Write bigram counts to `writer`, in the format expected by [`read_bigrams()`]
+pub fn write_bigrams<'a>(
+    mut writer: impl Write,
+    bigrams: impl IntoIterator<Item = ((&'a str, &'a str), f64)>,
+) -> Result<(), ModelError> {
+    for ((first, second), count) in bigrams {
+        writeln!(writer, "{first} {second}\t{count}")?;
+    }
+    Ok(writer.flush()?)
+}
+
+pub(crate) fn for_each_line(
+    reader: impl BufRead,
+    mut f: impl FnMut(&str) -> Result<(), &'static str>,
+) -> Result<(), ModelError> {
+    let mut reader = decode(reader)?;
+    let mut ln = std::string::String::new();
+    let mut line = 0;
+    loop {
+        ln.clear();
+        if reader.read_line(&mut ln)? == 0 {
+            return Ok(());
+        }
+
+        line += 1;
+        let trimmed = ln.trim_end_matches(['\n', '\r']);
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        f(trimmed).map_err(|reason| ModelError::Parse {
+            line,
+            reason,
+            path: None,
+        })?;
+    }
+}
+
+fn split_count(ln: &str) -> Result<(&str, f64), &'static str> {
+    let (words, count) = ln.rsplit_once('\t').ok_or("no tab found")?;
+    let count = f64::from_str(count.trim()).map_err(|_| "invalid count")?;
+    Ok((words, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut buf = Vec::new();
+        write_unigrams(&mut buf, [("choose", 80_000.0), ("spain", 20_000.0)]).unwrap();
+        assert_eq!(buf, b"choose\t80000\nspain\t20000\n");
+        let unigrams = read_unigrams(&buf[..]).unwrap();
+        assert_eq!(
+            unigrams,
+            [("choose".into(), 80_000.0), ("spain".into(), 20_000.0)]
+        );
+
+        let mut buf = Vec::new();
+        write_bigrams(&mut buf, [(("choose", "spain"), 7.0)]).unwrap();
+        assert_eq!(buf, b"choose spain\t7\n");
+        let bigrams = read_bigrams(&buf[..]).unwrap();
+        assert_eq!(bigrams, [(("choose".into(), "spain".into()), 7.0)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = read_unigrams(&b"choose\t80000\n\nspain 20000\n"[..]).unwrap_err();
+        assert!(matches!(err, ModelError::Parse { line: 3, .. }));
+        let err = read_bigrams(&b"choose\t7\n"[..]).unwrap_err();
+        assert!(matches!(err, ModelError::Parse { line: 1, .. }));
+        let err = read_unigrams(&b"choose\tmany\n"[..]).unwrap_err();
+        assert_eq!(err.to_string(), "parse error at line 1: invalid count");
+    }
+}
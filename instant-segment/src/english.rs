@@ -0,0 +1,30 @@
+use crate::Segmenter;
+
+impl Segmenter {
+    /// Create `Segmenter` from the English model shipped with `instant-segment-english`
+    ///
+    /// The model is built from the unigram counts in `data/en-unigrams.txt` and embedded in the
+    /// binary when the `english` feature is enabled. It has no bigrams, so each word is scored by
+    /// its own frequency only; build a [`Segmenter`] from both count files for better results.
+    /// The model is decompressed on every call, so the result should be reused.
+    pub fn english() -> Self {
+        // The embedded model is loaded by `test_english()`, so this can't fail at runtime
+        Self::load(instant_segment_english::MODEL).expect("invalid embedded model")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::segment;
+    use crate::Segmenter;
+
+    #[test]
+    fn test_english() {
+        let segmenter = Segmenter::english();
+        assert_eq!(segmenter.bigram_len(), 0);
+        assert_eq!(
+            segment(&segmenter, "thisisatest"),
+            ["this", "is", "a", "test"]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Evaluate segmentation quality against a gold standard
+//!
+//! Gold files contain one segmented example per line, with the words separated by spaces. Empty
+//! lines and lines starting with `#` are skipped.
+
+use std::cmp::Ordering;
+use std::fmt::{self, Write as _};
+use std::io::BufRead;
+
+use smartstring::alias::String;
+
+use crate::compression::decode;
+use crate::{InvalidCharacter, ModelError, Search, Segmenter};
+
+impl Segmenter {
+    /// Evaluate [`Segmenter::segment()`] against the `gold` segmentations
+    pub fn evaluate<S: AsRef<str>>(&self, gold: &[Vec<S>]) -> Report {
+        let mut search = Search::default();
+        evaluate(gold, |input| {
+            let words = self.segment(input, &mut search)?;
+            Ok(words.map(String::from).collect())
+        })
+    }
+}
+
+/// Evaluate the segmentation function `segment` against the `gold` segmentations
+///
+/// This can be used to evaluate other segmentation methods or configurations than
+/// [`Segmenter::segment()`].
+pub fn evaluate<S: AsRef<str>>(
+    gold: &[Vec<S>],
+    mut segment: impl FnMut(&str) -> Result<Vec<String>, InvalidCharacter>,
+) -> Report {
+    let mut report = Report::default();
+    let mut input = std::string::String::new();
+    for expected in gold {
+        input.clear();
+        expected
+            .iter()
+            .for_each(|word| input.push_str(word.as_ref()));
+        let expected = expected.iter().map(|w| w.as_ref()).collect::<Vec<_>>();
+        let expected_spans = spans(&expected);
+
+        report.examples += 1;
+        let actual = match segment(&input) {
+            Ok(actual) => actual,
+            Err(_) => {
+                report.errors += 1;
+                report.boundaries.false_negatives += expected.len().saturating_sub(1);
+                report.words.false_negatives += expected.len();
+                report.diffs.push(Diff {
+                    expected: expected.iter().map(|&w| w.into()).collect(),
+                    actual: None,
+                });
+                continue;
+            }
+        };
+
+        let actual_spans = spans(&actual);
+        report
+            .words
+            .count(&expected_spans, &actual_spans, |&span| span);
+        // Boundaries are the word ends, except for the end of the input
+        report.boundaries.count(
+            &expected_spans[..expected_spans.len().saturating_sub(1)],
+            &actual_spans[..actual_spans.len().saturating_sub(1)],
+            |&(_, end)| end,
+        );
+
+        match expected_spans == actual_spans {
+            true => report.exact_matches += 1,
+            false => report.diffs.push(Diff {
+                expected: expected.iter().map(|&w| w.into()).collect(),
+                actual: Some(actual),
+            }),
+        }
+    }
+
+    report
+}
+
+/// Read gold segmentations from `reader`
+pub fn read_gold(reader: impl BufRead) -> Result<Vec<Vec<String>>, ModelError> {
+    let mut gold = Vec::new();
+    for ln in decode(reader)?.lines() {
+        let ln = ln?;
+        let ln = ln.trim();
+        if ln.is_empty() || ln.starts_with('#') {
+            continue;
+        }
+
+        gold.push(ln.split_whitespace().map(String::from).collect());
+    }
+
+    Ok(gold)
+}
+
+/// Render the summaries of several reports as a table, one column per report
+///
+/// Use this to compare different models or configurations on the same gold data.
+pub fn side_by_side(reports: &[(&str, &Report)]) -> std::string::String {
+    type Metric = fn(&Report) -> f64;
+    let rows: [(&str, Metric); 7] = [
+        ("exact match", Report::accuracy),
+        ("boundary precision", |r| r.boundaries.precision()),
+        ("boundary recall", |r| r.boundaries.recall()),
+        ("boundary F1", |r| r.boundaries.f1()),
+        ("word precision", |r| r.words.precision()),
+        ("word recall", |r| r.words.recall()),
+        ("word F1", |r| r.words.f1()),
+    ];
+
+    let width = reports
+        .iter()
+        .map(|(name, _)| name.len())
+        .fold(8, usize::max);
+
+    let mut table = format!("{:20}", "");
+    for (name, _) in reports {
+        write!(table, " {name:>width$}").unwrap();
+    }
+    table.push('\n');
+
+    for (label, metric) in rows.iter() {
+        write!(table, "{label:20}").unwrap();
+        for (_, report) in reports {
+            write!(table, " {:>width$.4}", metric(report)).unwrap();
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+/// Results of evaluating a segmentation method against a gold standard
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// The number of examples evaluated
+    pub examples: usize,
+    /// The number of examples segmented exactly as in the gold standard
+    pub exact_matches: usize,
+    /// The number of examples that could not be segmented due to invalid characters
+    pub errors: usize,
+    /// Counts for the word boundaries inside the examples
+    pub boundaries: Counts,
+    /// Counts for the words, where a word only matches if both of its boundaries match
+    pub words: Counts,
+    /// The examples that were not segmented exactly as in the gold standard
+    pub diffs: Vec<Diff>,
+}
+
+impl Report {
+    /// The fraction of examples segmented exactly as in the gold standard
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.exact_matches, self.examples)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "exact match: {:.4} ({}/{}, {} errors)",
+            self.accuracy(),
+            self.exact_matches,
+            self.examples,
+            self.errors
+        )?;
+        writeln!(f, "boundaries:  {}", self.boundaries)?;
+        write!(f, "words:       {}", self.words)
+    }
+}
+
+/// Matches between predicted and gold items, such as word boundaries
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Counts {
+    /// Items found in both the prediction and the gold standard
+    pub true_positives: usize,
+    /// Items only found in the prediction
+    pub false_positives: usize,
+    /// Items only found in the gold standard
+    pub false_negatives: usize,
+}
+
+impl Counts {
+    /// The fraction of predicted items that are correct
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    /// The fraction of gold items that were predicted
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    /// The harmonic mean of precision and recall
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        match precision + recall {
+            sum if sum > 0.0 => 2.0 * precision * recall / sum,
+            _ => 0.0,
+        }
+    }
+
+    /// Count the matches between sorted `expected` and `actual` items
+    fn count<T, K: Ord>(&mut self, expected: &[T], actual: &[T], key: impl Fn(&T) -> K) {
+        let (mut i, mut j) = (0, 0);
+        while i < expected.len() && j < actual.len() {
+            match key(&expected[i]).cmp(&key(&actual[j])) {
+                Ordering::Less => {
+                    self.false_negatives += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    self.false_positives += 1;
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    self.true_positives += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        self.false_negatives += expected.len() - i;
+        self.false_positives += actual.len() - j;
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "precision {:.4}, recall {:.4}, F1 {:.4}",
+            self.precision(),
+            self.recall(),
+            self.f1()
+        )
+    }
+}
+
+/// An example that was not segmented as in the gold standard
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diff {
+    /// The gold segmentation
+    pub expected: Vec<String>,
+    /// The predicted segmentation, or `None` if the input contained invalid characters
+    pub actual: Option<Vec<String>>,
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "expected: {}", self.expected.join(" "))?;
+        match &self.actual {
+            Some(actual) => write!(f, "actual:   {}", actual.join(" ")),
+            None => write!(f, "actual:   (invalid input)"),
+        }
+    }
+}
+
+/// Byte ranges of the `words` in their concatenation, as `(start, end)` pairs
+fn spans<S: AsRef<str>>(words: &[S]) -> Vec<(usize, usize)> {
+    let mut start = 0;
+    words
+        .iter()
+        .map(|word| {
+            let end = start + word.as_ref().len();
+            let span = (start, end);
+            start = end;
+            span
+        })
+        .collect()
+}
+
+fn ratio(num: usize, denom: usize) -> f64 {
+    match denom {
+        0 => 0.0,
+        _ => num as f64 / denom as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    #[test]
+    fn test_evaluate() {
+        let segmenter = choose_spain();
+
+        let gold = read_gold(&b"# comment\nchoose spain\n\nchooses pain\nChoose spain\n"[..]);
+        let gold = gold.unwrap();
+        assert_eq!(gold.len(), 3);
+
+        let report = segmenter.evaluate(&gold);
+        assert_eq!(
+            (report.examples, report.exact_matches, report.errors),
+            (3, 1, 1)
+        );
+        assert!((report.accuracy() - 1.0 / 3.0).abs() < 1e-9);
+        let boundaries = Counts {
+            true_positives: 1,
+            false_positives: 1,
+            false_negatives: 2,
+        };
+        assert_eq!(report.boundaries, boundaries);
+        assert!((report.boundaries.precision() - 0.5).abs() < 1e-9);
+        assert!((report.boundaries.recall() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.words.true_positives, 2);
+        assert_eq!(report.words.false_negatives, 4);
+
+        assert_eq!(report.diffs.len(), 2);
+        assert_eq!(
+            report.diffs[0].to_string(),
+            "expected: chooses pain\nactual:   choose spain"
+        );
+        assert_eq!(report.diffs[1].actual, None);
+
+        let always_split = evaluate(&gold, |input| {
+            Ok(input.chars().map(|c| c.to_string().into()).collect())
+        });
+        let table = side_by_side(&[("default", &report), ("chars", &always_split)]);
+        assert!(table.contains("default"));
+        assert_eq!(table.lines().count(), 8);
+    }
+
+    #[test]
+    fn test_evaluate_empty() {
+        let gold = read_gold(&b"# only a comment\n\n"[..]).unwrap();
+        assert!(gold.is_empty());
+
+        // Ratios without any examples must not be NaN
+        let report = choose_spain().evaluate(&gold);
+        assert!(!report.accuracy().is_nan());
+        assert!(!report.words.f1().is_nan());
+
+        // Single words have no boundaries to find
+        let report = choose_spain().evaluate(&[vec!["pain"], vec!["choose"]]);
+        assert_eq!((report.examples, report.exact_matches), (2, 2));
+        assert_eq!(report.boundaries, Counts::default());
+        assert_eq!(report.words.f1(), 1.0);
+    }
+}
@@ -0,0 +1,237 @@
+//! Splitting of source code identifiers
+//!
+//! Identifiers like `getHTTPResponsecode` or `MAX_BUFFERSIZE` are first split into chunks using
+//! the cues the author left behind (separators like `_`, case transitions and acronym runs).
+//! Chunks that are not acronyms are then segmented further using the [`Segmenter`].
+
+use smartstring::alias::String;
+
+use crate::{Ascii, Search, SegmentState, Segmenter};
+
+impl Segmenter {
+    /// Segment a source code identifier into lowercase words
+    ///
+    /// The identifier is first split on any non-alphanumeric character (like `_` or `-`), on
+    /// transitions from lowercase letters or digits to uppercase letters and at the end of
+    /// acronym runs (so `getHTTPResponse` yields `get`, `http` and `response`). Acronyms
+    /// embedded in camel case identifiers are kept intact, while all other chunks are lowercased
+    /// and passed through the regular segmentation. Since the segmenter only handles ASCII
+    /// input, chunks containing non-ASCII letters (like `über` in `überCool`) are lowercased and
+    /// kept whole instead.
+    ///
+    /// Consider merging [`CODE_UNIGRAMS`] into the segmenter via [`Segmenter::add_unigrams()`]
+    /// to improve results for programming vocabulary.
+    pub fn segment_identifier(&self, input: &str, search: &mut Search) -> Vec<String> {
+        let mut words = Vec::new();
+        for chunk in chunks(input) {
+            if !chunk.text.is_ascii() {
+                words.push(chunk.text.to_lowercase().into());
+                continue;
+            }
+
+            let mut lower = String::from(chunk.text);
+            lower.make_ascii_lowercase();
+            if chunk.acronym {
+                words.push(lower);
+                continue;
+            }
+
+            // Chunks only contain ASCII letters and digits, so we can skip validation
+            SegmentState::new(Ascii(lower.as_bytes()), self, search).run();
+            words.append(&mut search.result);
+        }
+
+        words
+    }
+}
+
+/// Split `input` into chunks based on separators, case transitions and acronym runs
+fn chunks(input: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    for part in input.split(|c: char| !c.is_alphanumeric()) {
+        if part.is_empty() {
+            continue;
+        }
+
+        // Parts without any lowercase letters (like `MAXBUFFERSIZE`) are not camel case,
+        // so there are no case transitions to split on.
+        let bytes = part.as_bytes();
+        if !bytes.iter().any(|b| b.is_ascii_lowercase()) {
+            chunks.push(Chunk {
+                text: part,
+                acronym: false,
+            });
+            continue;
+        }
+
+        // Splits only happen next to ASCII letters, so `start` and `end` are always on a char
+        // boundary even if `part` contains non-ASCII letters.
+        let mut start = 0;
+        for i in 1..bytes.len() {
+            let (prev, cur) = (bytes[i - 1], bytes[i]);
+            let split = if cur.is_ascii_uppercase() {
+                // `getHttp` or `utf8Decoder`
+                !prev.is_ascii_uppercase()
+            } else if cur.is_ascii_lowercase() {
+                // `HTTPResponse`: the last uppercase letter starts the next word
+                prev.is_ascii_uppercase() && i - start > 1 && bytes[i - 2].is_ascii_uppercase()
+            } else {
+                false
+            };
+
+            if !split {
+                continue;
+            }
+
+            let end = match cur.is_ascii_lowercase() {
+                true => i - 1,
+                false => i,
+            };
+
+            chunks.push(Chunk::new(&part[start..end]));
+            start = end;
+        }
+
+        chunks.push(Chunk::new(&part[start..]));
+    }
+
+    chunks
+}
+
+#[derive(Debug, PartialEq)]
+struct Chunk<'a> {
+    text: &'a str,
+    acronym: bool,
+}
+
+impl<'a> Chunk<'a> {
+    fn new(text: &'a str) -> Self {
+        let upper = text.bytes().filter(|b| b.is_ascii_uppercase()).count();
+        Self {
+            text,
+            acronym: upper > 1 && !text.bytes().any(|b| b.is_ascii_lowercase()),
+        }
+    }
+}
+
+/// Unigram counts for common programming vocabulary
+///
+/// The counts are on the same scale as the `en-unigrams.txt` file that the English model is built
+/// from, such that they can be merged into a segmenter built from that file using
+/// [`Segmenter::add_unigrams()`].
+///
+/// The words were picked by hand and their counts are rough estimates rather than measurements:
+/// 2,000,000 ranks a word among the 12,500 most frequent words in `en-unigrams.txt`, 1,000,000
+/// among the 19,000 most frequent and 500,000 among the 27,000 most frequent. This is high
+/// enough to win over splits into short English words (`buf` over `bu` and `f`) without
+/// outranking everyday words. When merging into a segmenter built from other counts, scale
+/// these by the ratio of that segmenter's [`Segmenter::total_count()`] to the total of
+/// `en-unigrams.txt` (about 3.9 × 10¹¹), and raise or lower individual counts if identifiers
+/// from your code base are split too eagerly or not enough.
+pub const CODE_UNIGRAMS: &[(&str, f64)] = &[
+    ("args", 1_000_000.0),
+    ("async", 1_000_000.0),
+    ("auth", 1_000_000.0),
+    ("bool", 1_000_000.0),
+    ("btn", 500_000.0),
+    ("buf", 1_000_000.0),
+    ("cfg", 500_000.0),
+    ("cmd", 1_000_000.0),
+    ("config", 2_000_000.0),
+    ("ctx", 500_000.0),
+    ("dst", 500_000.0),
+    ("enum", 1_000_000.0),
+    ("env", 1_000_000.0),
+    ("func", 1_000_000.0),
+    ("http", 2_000_000.0),
+    ("idx", 500_000.0),
+    ("impl", 500_000.0),
+    ("init", 1_000_000.0),
+    ("json", 2_000_000.0),
+    ("len", 1_000_000.0),
+    ("malloc", 500_000.0),
+    ("msg", 1_000_000.0),
+    ("num", 1_000_000.0),
+    ("param", 1_000_000.0),
+    ("params", 1_000_000.0),
+    ("printf", 500_000.0),
+    ("ptr", 1_000_000.0),
+    ("regex", 500_000.0),
+    ("repo", 1_000_000.0),
+    ("req", 500_000.0),
+    ("src", 1_000_000.0),
+    ("stderr", 500_000.0),
+    ("stdin", 500_000.0),
+    ("stdout", 500_000.0),
+    ("str", 1_000_000.0),
+    ("struct", 1_000_000.0),
+    ("sync", 1_000_000.0),
+    ("tmp", 1_000_000.0),
+    ("usize", 500_000.0),
+    ("utf", 1_000_000.0),
+    ("uuid", 500_000.0),
+    ("vec", 500_000.0),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(input: &str) -> Vec<(&str, bool)> {
+        chunks(input)
+            .into_iter()
+            .map(|chunk| (chunk.text, chunk.acronym))
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(
+            texts("getHTTPResponsecode"),
+            [("get", false), ("HTTP", true), ("Responsecode", false)]
+        );
+        assert_eq!(texts("maxbuffersize"), [("maxbuffersize", false)]);
+        assert_eq!(
+            texts("MAX_BUFFERSIZE"),
+            [("MAX", false), ("BUFFERSIZE", false)]
+        );
+        assert_eq!(texts("utf8Decoder"), [("utf8", false), ("Decoder", false)]);
+        assert_eq!(
+            texts("__parseURL2json"),
+            [("parse", false), ("URL2json", false)]
+        );
+        assert_eq!(
+            texts("ALargeThing"),
+            [("A", false), ("Large", false), ("Thing", false)]
+        );
+        assert_eq!(texts("über-cool"), [("über", false), ("cool", false)]);
+        assert_eq!(texts("überCool"), [("über", false), ("Cool", false)]);
+    }
+
+    #[test]
+    fn test_segment_identifier() {
+        let mut segmenter = crate::tests::segmenter(
+            &[
+                ("response", 10_000.0),
+                ("code", 10_000.0),
+                ("get", 20_000.0),
+            ],
+            &[],
+        );
+        segmenter.add_unigrams(
+            CODE_UNIGRAMS
+                .iter()
+                .map(|&(word, count)| (word.into(), count)),
+        );
+
+        let mut search = Search::default();
+        assert_eq!(
+            segmenter.segment_identifier("getHTTPResponsecode", &mut search),
+            ["get", "http", "response", "code"]
+        );
+        assert_eq!(
+            segmenter.segment_identifier("ÜberResponse", &mut search),
+            ["über", "response"]
+        );
+    }
+}
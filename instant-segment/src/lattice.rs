@@ -0,0 +1,215 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::{Ascii, InvalidCharacter, Search, SegmentState, Segmenter};
+
+impl Segmenter {
+    /// Build the word lattice for `input`
+    ///
+    /// The [`Lattice`] contains every word considered while segmenting `input`, along with the
+    /// score it contributes to any path that contains it. The bigram part of each score is based
+    /// on the best word ending where the word starts, as in [`Segmenter::segment()`].
+    pub fn lattice(&self, input: &str, search: &mut Search) -> Result<Lattice, InvalidCharacter> {
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
+        let mut edges = Vec::new();
+        if !input.is_empty() {
+            state.run();
+            for end in 1..=input.len() {
+                for start in end.saturating_sub(self.limit)..end {
+                    let score = state.edge_score(start, end);
+                    edges.push(Edge { start, end, score });
+                }
+            }
+        }
+
+        Ok(Lattice::new(input.into(), edges))
+    }
+}
+
+/// Word lattice for a segmented string, as built by [`Segmenter::lattice()`]
+///
+/// Nodes in the lattice are byte positions in the input text, edges are candidate words. Every
+/// path from position `0` to the end of the text is a possible segmentation.
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lattice {
+    text: std::string::String,
+    edges: Vec<Edge>,
+}
+
+impl Lattice {
+    fn new(text: std::string::String, mut edges: Vec<Edge>) -> Self {
+        edges.sort_by_key(|edge| (edge.start, edge.end));
+        Self { text, edges }
+    }
+
+    /// The text this lattice was built from
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// All edges in the lattice, ordered by start and end position
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The edges starting at byte position `start`, ordered by end position
+    pub fn edges_from(&self, start: usize) -> &[Edge] {
+        let from = self.edges.partition_point(|edge| edge.start < start);
+        let to = self.edges.partition_point(|edge| edge.start <= start);
+        &self.edges[from..to]
+    }
+
+    /// The word for the given `edge`
+    pub fn word(&self, edge: &Edge) -> &str {
+        &self.text[edge.span()]
+    }
+
+    /// The score of the best path through the lattice
+    ///
+    /// This matches the score of the segmentation found by [`Segmenter::segment()`].
+    pub fn best_score(&self) -> f64 {
+        let (forward, _) = self.best_paths();
+        forward[self.text.len()]
+    }
+
+    /// Remove edges that are not part of any path within `margin` of the best path's score
+    ///
+    /// Edges on the best path are always kept, even with a `margin` of `0.0`. Returns the number
+    /// of edges removed.
+    pub fn prune(&mut self, margin: f64) -> usize {
+        let (forward, backward) = self.best_paths();
+        let threshold = forward[self.text.len()] - margin - EPSILON;
+        let before = self.edges.len();
+        self.edges
+            .retain(|edge| forward[edge.start] + edge.score + backward[edge.end] >= threshold);
+        before - self.edges.len()
+    }
+
+    /// Render the lattice in the Graphviz DOT format
+    pub fn to_dot(&self) -> std::string::String {
+        let mut dot = std::string::String::from("digraph lattice {\n    rankdir=LR;\n");
+        for edge in &self.edges {
+            writeln!(
+                dot,
+                "    {} -> {} [label=\"{} ({:.3})\"];",
+                edge.start,
+                edge.end,
+                self.word(edge),
+                edge.score
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Best path scores from the start to each position and from each position to the end
+    fn best_paths(&self) -> (Vec<f64>, Vec<f64>) {
+        let len = self.text.len();
+        let mut forward = vec![f64::NEG_INFINITY; len + 1];
+        forward[0] = 0.0;
+        for edge in &self.edges {
+            let score = forward[edge.start] + edge.score;
+            if score > forward[edge.end] {
+                forward[edge.end] = score;
+            }
+        }
+
+        let mut backward = vec![f64::NEG_INFINITY; len + 1];
+        backward[len] = 0.0;
+        for edge in self.edges.iter().rev() {
+            let score = edge.score + backward[edge.end];
+            if score > backward[edge.start] {
+                backward[edge.start] = score;
+            }
+        }
+
+        (forward, backward)
+    }
+}
+
+/// Tolerance for rounding differences between path scores summed in a different order
+const EPSILON: f64 = 1e-9;
+
+/// A candidate word in a [`Lattice`]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    /// Byte position where the word starts
+    pub start: usize,
+    /// Byte position where the word ends
+    pub end: usize,
+    /// The score this word contributes to a path containing it
+    pub score: f64,
+}
+
+impl Edge {
+    /// Byte range of the word in the lattice text
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    #[test]
+    fn test_lattice() {
+        let mut segmenter = choose_spain();
+        let mut search = Search::default();
+        let segments = segmenter.segment("choosespain", &mut search).unwrap();
+        let score = segments.score();
+
+        let mut lattice = segmenter.lattice("choosespain", &mut search).unwrap();
+        assert_eq!(lattice.edges().len(), 66);
+        assert!((lattice.best_score() - score).abs() < 1e-9);
+
+        let words = lattice
+            .edges_from(6)
+            .iter()
+            .map(|edge| lattice.word(edge))
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["s", "sp", "spa", "spai", "spain"]);
+        assert!(lattice.edges_from(11).is_empty());
+        assert!(lattice.to_dot().contains("0 -> 6 [label=\"choose"));
+
+        // An infinite margin keeps everything, a zero margin only the best path
+        assert_eq!(lattice.prune(f64::INFINITY), 0);
+        assert!(lattice.prune(0.0) > 0);
+        let words = lattice
+            .edges()
+            .iter()
+            .map(|edge| lattice.word(edge))
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["choose", "spain"]);
+        assert!((lattice.best_score() - score).abs() < 1e-9);
+
+        // The word length limit bounds the edges ending at each position
+        segmenter.set_limit(2);
+        let lattice = segmenter.lattice("choose", &mut search).unwrap();
+        assert_eq!(lattice.edges().len(), 11);
+        assert!(lattice.edges().iter().all(|edge| edge.span().len() <= 2));
+
+        let empty = segmenter.lattice("", &mut search).unwrap();
+        assert!(empty.edges().is_empty());
+        assert_eq!(empty.best_score(), 0.0);
+        assert_eq!(empty.to_dot(), "digraph lattice {\n    rankdir=LR;\n}\n");
+    }
+
+    #[test]
+    fn test_prune_rounding() {
+        // Summing the scores of the only path from the start or from the end rounds differently
+        let edges = [(0, 1, -0.54), (1, 2, -0.86), (2, 3, -0.23)]
+            .iter()
+            .map(|&(start, end, score)| Edge { start, end, score })
+            .collect();
+        let mut lattice = Lattice::new("abc".into(), edges);
+        assert_eq!(lattice.prune(0.0), 0);
+    }
+}
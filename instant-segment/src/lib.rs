@@ -5,10 +5,37 @@ use std::str;
 use serde::{Deserialize, Serialize};
 use smartstring::alias::String;
 
-#[cfg(feature = "test-cases")]
+#[cfg(feature = "rayon")]
+mod batch;
+mod beam;
+pub use beam::{Beam, InvalidBeam};
+pub mod compression;
+mod confidence;
+pub use confidence::Confidence;
+pub mod counts;
+#[cfg(feature = "english")]
+mod english;
+pub mod eval;
+pub mod identifier;
+mod lattice;
+pub use lattice::{Edge, Lattice};
+mod mixture;
+pub use mixture::{Interpolation, InvalidWeights, Mixture};
+pub mod model;
+mod n_best;
+pub use n_best::MAX_N_BEST;
+pub mod ngrams;
+pub mod prune;
+mod stats;
+pub use stats::MemoryUsage;
+mod stream;
+pub use stream::StreamSegmenter;
+#[cfg(any(test, feature = "test-cases"))]
 pub mod test_cases;
 #[cfg(feature = "__test_data")]
 pub mod test_data;
+pub mod tune;
+pub mod url;
 
 /// Central data structure used to calculate word probabilities
 #[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
@@ -20,6 +47,9 @@ pub struct Segmenter {
     // Base-10 logarithm of the total count of unigrams
     uni_total_log10: f64,
     limit: usize,
+    // Defaults when missing, for data serialized by older releases in self-describing formats
+    #[cfg_attr(feature = "with-serde", serde(default))]
+    params: Params,
 }
 
 impl Segmenter {
@@ -65,20 +95,22 @@ impl Segmenter {
             uni_total_log10: uni_total.log10(),
             scores,
             limit: DEFAULT_LIMIT,
+            params: Params::default(),
         }
     }
 
     /// Segment the text in `input`
     ///
     /// Requires that the input `text` consists of lowercase ASCII characters only. Otherwise,
-    /// returns `Err(InvalidCharacter)`. The `search` parameter contains caches that are used
-    /// segmentation; passing it in allows the callers to reuse the cache allocations.
+    /// returns `Err(InvalidCharacter)`; use [`clean()`] to prepare arbitrary text. The `search`
+    /// parameter contains caches that are used segmentation; passing it in allows the callers to
+    /// reuse the cache allocations.
     pub fn segment<'a>(
         &self,
         input: &str,
         search: &'a mut Search,
     ) -> Result<Segments<'a>, InvalidCharacter> {
-        let state = SegmentState::new(Ascii::new(input)?, self, search);
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
         let score = match input {
             "" => 0.0,
             _ => state.run(),
@@ -87,6 +119,7 @@ impl Segmenter {
         Ok(Segments {
             iter: search.result.iter(),
             score,
+            pruned: false,
         })
     }
 
@@ -104,7 +137,36 @@ impl Segmenter {
         Some(score)
     }
 
+    /// Segment the text in `input`, returning an owned result with per-word details
+    ///
+    /// Like [`Segmenter::segment()`], but the returned [`Segmentation`] also contains the
+    /// [`WordDetails`] for every word.
+    pub fn segment_detailed(
+        &self,
+        input: &str,
+        search: &mut Search,
+    ) -> Result<Segmentation, InvalidCharacter> {
+        let mut segmentation = Segmentation::from(self.segment(input, search)?);
+        let mut prev = None;
+        let details = segmentation
+            .words
+            .iter()
+            .map(|word| {
+                let (score, source) = self.score_source(word, prev);
+                prev = Some(word);
+                WordDetails { score, source }
+            })
+            .collect();
+
+        segmentation.details = Some(details);
+        Ok(segmentation)
+    }
+
     fn score(&self, word: &str, previous: Option<&str>) -> f64 {
+        self.score_source(word, previous).0
+    }
+
+    fn score_source(&self, word: &str, previous: Option<&str>) -> (f64, WordSource) {
         let (uni, bi_scores) = match self.scores.get(word) {
             Some((uni, bi_scores)) => (uni, bi_scores),
             // Penalize words not found in the unigrams according
@@ -119,36 +181,101 @@ impl Segmenter {
             // there "should" have been in the non-word's place.
             //
             // See <https://github.com/instant-labs/instant-segment/issues/53>.
+            //
+            // The constants used here can be tuned through `Params`.
             None => {
                 let word_len = word.len() as f64;
-                let word_count = word_len / 5.0;
-                return (1.0 - self.uni_total_log10 - word_len) * word_count;
+                let word_count = word_len / self.params.unknown_word_len;
+                let score =
+                    (self.params.unknown_base - self.uni_total_log10 - word_len) * word_count;
+                return (score, WordSource::Unknown);
             }
         };
 
-        if let Some(prev) = previous {
+        // With a weight of 0, bigrams don't contribute to the score
+        if let Some(prev) = previous.filter(|_| self.params.bigram_weight > 0.0) {
             if let Some(bi) = bi_scores.get(prev) {
                 if let Some((uni_prev, _)) = self.scores.get(prev) {
                     // Conditional probability of the word given the previous
                     // word. The technical name is "stupid backoff" and it's
                     // not a probability distribution but it works well in practice.
-                    return bi - uni_prev;
+                    let score = bi - uni_prev;
+                    let weight = self.params.bigram_weight;
+                    if weight >= 1.0 {
+                        return (score, WordSource::Bigram);
+                    }
+
+                    // Linear interpolation with the unigram probability
+                    let mixed = weight * 10f64.powf(score) + (1.0 - weight) * 10f64.powf(*uni);
+                    return (mixed.log10(), WordSource::Bigram);
                 }
             }
         }
 
-        *uni
+        (*uni, WordSource::Unigram)
+    }
+
+    /// Merge additional unigram counts into the segmenter
+    ///
+    /// The counts should be on the same scale as the unigram counts the `Segmenter` was created
+    /// from. Counts for words that are already known are added to the existing counts. This can
+    /// be used to overlay domain-specific vocabulary (like [`identifier::CODE_UNIGRAMS`]) onto a
+    /// general purpose model.
+    pub fn add_unigrams<U>(&mut self, unigrams: U)
+    where
+        U: IntoIterator<Item = (String, f64)>,
+    {
+        // Convert the unigram scores back to counts, so we can recalculate the relative
+        // frequencies against the new total. Bigram scores are relative to the bigram total,
+        // which doesn't change.
+        let mut uni_total = 10f64.powf(self.uni_total_log10);
+        for (uni, _) in self.scores.values_mut() {
+            *uni = 10f64.powf(*uni + self.uni_total_log10);
+        }
+
+        for (word, uni) in unigrams {
+            self.scores
+                .entry(word)
+                .or_insert_with(|| (0.0, HashMap::default()))
+                .0 += uni;
+            uni_total += uni;
+        }
+
+        for (uni, _) in self.scores.values_mut() {
+            *uni = (*uni / uni_total).log10();
+        }
+        self.uni_total_log10 = uni_total.log10();
     }
 
     /// Customize the word length `limit`
     pub fn set_limit(&mut self, limit: usize) {
         self.limit = limit;
     }
+
+    /// The word length limit
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The scoring parameters
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Customize the scoring parameters
+    ///
+    /// Returns an error, leaving the parameters unchanged, if any of the `params` is out of range.
+    pub fn set_params(&mut self, params: Params) -> Result<(), InvalidParams> {
+        params.validate()?;
+        self.params = params;
+        Ok(())
+    }
 }
 
 pub struct Segments<'a> {
     iter: std::slice::Iter<'a, String>,
     score: f64,
+    pruned: bool,
 }
 
 impl Segments<'_> {
@@ -156,6 +283,11 @@ impl Segments<'_> {
     pub fn score(&self) -> f64 {
         self.score
     }
+
+    /// Whether beam search skipped any candidates, see [`Segmenter::segment_beam()`]
+    pub fn pruned(&self) -> bool {
+        self.pruned
+    }
 }
 
 impl<'a> Iterator for Segments<'a> {
@@ -172,47 +304,142 @@ impl ExactSizeIterator for Segments<'_> {
     }
 }
 
+impl<'a> From<Segments<'a>> for Segmentation {
+    fn from(segments: Segments<'a>) -> Self {
+        let mut start = 0;
+        let (words, spans) = segments
+            .iter
+            .map(|word| {
+                let span = start..start + word.len();
+                start = span.end;
+                (word.clone(), span)
+            })
+            .unzip();
+
+        Self {
+            words,
+            spans,
+            score: segments.score,
+            details: None,
+            confidence: None,
+        }
+    }
+}
+
+/// Owned result of segmenting a string
+///
+/// Unlike [`Segments`], this does not borrow the [`Search`] it was created from, so it can be
+/// stored or sent across threads.
+#[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Segmentation {
+    words: Vec<String>,
+    spans: Vec<Range<usize>>,
+    score: f64,
+    details: Option<Vec<WordDetails>>,
+    confidence: Option<Confidence>,
+}
+
+impl Segmentation {
+    /// The words found in the segmented text
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Byte ranges of the words in the segmented text
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// Returns the score of the segmented text
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Per-word details, if requested through [`Segmenter::segment_detailed()`]
+    pub fn details(&self) -> Option<&[WordDetails]> {
+        self.details.as_deref()
+    }
+
+    /// Confidence in the segmentation, if requested through
+    /// [`Segmenter::segment_with_confidence()`]
+    pub fn confidence(&self) -> Option<&Confidence> {
+        self.confidence.as_ref()
+    }
+}
+
+/// Scoring details for a single word in a [`Segmentation`]
+#[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WordDetails {
+    /// The word's contribution to the score of the segmentation
+    pub score: f64,
+    /// How the word's score was determined
+    pub source: WordSource,
+}
+
+/// The kind of score used for a word
+#[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WordSource {
+    /// The word is not in the unigrams and was scored by the unknown word penalty
+    Unknown,
+    /// The word was scored by its unigram frequency
+    Unigram,
+    /// The word was scored by the frequency of the bigram with its preceding word
+    Bigram,
+}
+
 struct SegmentState<'a> {
-    data: &'a Segmenter,
+    data: Model<'a>,
     text: Ascii<'a>,
     search: &'a mut Search,
 }
 
 impl<'a> SegmentState<'a> {
-    fn new(text: Ascii<'a>, data: &'a Segmenter, search: &'a mut Search) -> Self {
+    fn new(text: Ascii<'a>, data: impl Into<Model<'a>>, search: &'a mut Search) -> Self {
         search.clear();
-        Self { data, text, search }
+        Self {
+            data: data.into(),
+            text,
+            search,
+        }
     }
 
-    fn run(self) -> f64 {
+    fn run(&mut self) -> f64 {
         for end in 1..=self.text.len() {
-            let start = end.saturating_sub(self.data.limit);
+            let start = end.saturating_sub(self.data.limit());
             for split in start..end {
-                let (prev, prev_score) = match split {
-                    0 => (None, 0.0),
-                    _ => {
-                        let prefix = self.search.candidates[split - 1];
-                        let word = &self.text[split - prefix.len..split];
-                        (Some(word), prefix.score)
-                    }
-                };
-
-                let word = &self.text[split..end];
-                let score = self.data.score(word, prev) + prev_score;
-                match self.search.candidates.get_mut(end - 1) {
-                    Some(cur) if cur.score < score => {
-                        cur.len = end - split;
-                        cur.score = score;
-                    }
-                    None => self.search.candidates.push(Candidate {
-                        len: end - split,
-                        score,
-                    }),
-                    _ => {}
-                }
+                self.update(split, end);
             }
         }
 
+        self.backtrack()
+    }
+
+    /// Update the candidate ending at `end` if the word starting at `split` improves it
+    fn update(&mut self, split: usize, end: usize) {
+        let prev_score = match split {
+            0 => 0.0,
+            _ => self.search.candidates[split - 1].score,
+        };
+
+        let score = self.edge_score(split, end) + prev_score;
+        match self.search.candidates.get_mut(end - 1) {
+            Some(cur) if cur.score < score => {
+                cur.len = end - split;
+                cur.score = score;
+            }
+            None => self.search.candidates.push(Candidate {
+                len: end - split,
+                score,
+            }),
+            _ => {}
+        }
+    }
+
+    /// Collect the words for the best candidate path into `search.result`
+    fn backtrack(&mut self) -> f64 {
         let mut end = self.text.len();
         let mut best = self.search.candidates[end - 1];
         let score = best.score;
@@ -231,6 +458,54 @@ impl<'a> SegmentState<'a> {
         self.search.result.reverse();
         score
     }
+
+    /// Score of the word `text[split..end]`, following the best candidate ending at `split`
+    fn edge_score(&self, split: usize, end: usize) -> f64 {
+        let prev = match split {
+            0 => None,
+            _ => {
+                let prefix = self.search.candidates[split - 1];
+                Some(&self.text[split - prefix.len..split])
+            }
+        };
+
+        self.data.score(&self.text[split..end], prev)
+    }
+}
+
+/// The model to score words against in a [`SegmentState`]
+#[derive(Clone, Copy)]
+enum Model<'a> {
+    Segmenter(&'a Segmenter),
+    Mixture(&'a Mixture),
+}
+
+impl Model<'_> {
+    fn score(&self, word: &str, previous: Option<&str>) -> f64 {
+        match self {
+            Self::Segmenter(segmenter) => segmenter.score(word, previous),
+            Self::Mixture(mixture) => mixture.score(word, previous),
+        }
+    }
+
+    fn limit(&self) -> usize {
+        match self {
+            Self::Segmenter(segmenter) => segmenter.limit,
+            Self::Mixture(mixture) => mixture.limit(),
+        }
+    }
+}
+
+impl<'a> From<&'a Segmenter> for Model<'a> {
+    fn from(segmenter: &'a Segmenter) -> Self {
+        Self::Segmenter(segmenter)
+    }
+}
+
+impl<'a> From<&'a Mixture> for Model<'a> {
+    fn from(mixture: &'a Mixture) -> Self {
+        Self::Mixture(mixture)
+    }
 }
 
 /// Search state for a [`Segmenter`]
@@ -238,12 +513,29 @@ impl<'a> SegmentState<'a> {
 pub struct Search {
     candidates: Vec<Candidate>,
     result: Vec<String>,
+    // Forward and backward log probabilities per position and the scores of the words
+    // considered, see `SegmentState::forward_backward()`
+    forward: Vec<f64>,
+    backward: Vec<f64>,
+    edges: Vec<f64>,
+    // Scratch space for `SegmentState::run_beam()`
+    beam: Vec<(f64, usize)>,
+    // The best paths to each position, stored back to back, with the index of the first path to
+    // each position in `path_starts`, see `Segmenter::segment_n_best()`
+    paths: Vec<Path>,
+    path_starts: Vec<usize>,
 }
 
 impl Search {
     fn clear(&mut self) {
         self.candidates.clear();
         self.result.clear();
+        self.forward.clear();
+        self.backward.clear();
+        self.edges.clear();
+        self.beam.clear();
+        self.paths.clear();
+        self.path_starts.clear();
     }
 
     #[doc(hidden)]
@@ -252,6 +544,16 @@ impl Search {
     }
 }
 
+/// A path in the n-best search, see `Segmenter::segment_n_best()`
+#[derive(Clone, Copy, Debug)]
+struct Path {
+    score: f64,
+    // Length of the last word on the path
+    len: usize,
+    // Index in `Search::paths` of the preceding path, at the position where the last word starts
+    back: usize,
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 struct Candidate {
     len: usize,
@@ -264,17 +566,23 @@ struct Ascii<'a>(&'a [u8]);
 impl<'a> Ascii<'a> {
     fn new(s: &'a str) -> Result<Self, InvalidCharacter> {
         let bytes = s.as_bytes();
+        Self::validate(bytes)?;
+        Ok(Self(bytes))
+    }
 
-        let valid = bytes
-            .iter()
-            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+    fn validate(bytes: &[u8]) -> Result<(), InvalidCharacter> {
+        let position = bytes.iter().position(|&b| !Self::is_valid(b));
 
-        match valid {
-            true => Ok(Self(bytes)),
-            false => Err(InvalidCharacter),
+        match position {
+            None => Ok(()),
+            Some(position) => Err(InvalidCharacter::new(bytes, position)),
         }
     }
 
+    fn is_valid(b: u8) -> bool {
+        b.is_ascii_lowercase() || b.is_ascii_digit()
+    }
+
     fn len(&self) -> usize {
         self.0.len()
     }
@@ -290,15 +598,252 @@ impl Index<Range<usize>> for Ascii<'_> {
     }
 }
 
+/// Convert `input` into text that can be segmented
+///
+/// ASCII letters are lowercased, all other characters except ASCII digits are removed.
+pub fn clean(input: &str) -> std::string::String {
+    input
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
 /// Error returned by [`Segmenter::segment`] when given an invalid character
-#[derive(Debug)]
-pub struct InvalidCharacter;
+///
+/// Only lowercase ASCII letters and digits are allowed in the input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidCharacter {
+    position: usize,
+    character: char,
+}
+
+impl InvalidCharacter {
+    fn new(bytes: &[u8], position: usize) -> Self {
+        // Input from `StreamSegmenter` may not be valid UTF-8, so decode lossily
+        let end = bytes.len().min(position + 4);
+        let character = std::string::String::from_utf8_lossy(&bytes[position..end])
+            .chars()
+            .next()
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        Self {
+            position,
+            character,
+        }
+    }
+
+    /// Find all invalid characters in `input`, in order
+    pub fn all(input: &str) -> Vec<Self> {
+        input
+            .char_indices()
+            .filter(|&(_, c)| !(c.is_ascii() && Ascii::is_valid(c as u8)))
+            .map(|(position, character)| Self {
+                position,
+                character,
+            })
+            .collect()
+    }
+
+    /// Byte offset of the first invalid character in the input
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The first invalid character in the input
+    pub fn character(&self) -> char {
+        self.character
+    }
+}
 
 impl std::error::Error for InvalidCharacter {}
 
 impl std::fmt::Display for InvalidCharacter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("invalid character")
+        write!(
+            f,
+            "invalid character {:?} at byte {}",
+            self.character, self.position
+        )
+    }
+}
+
+/// Error returned for [`Params`] or word length limits that are out of range
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidParams(&'static str);
+
+impl std::error::Error for InvalidParams {}
+
+impl std::fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid scoring parameters: {}", self.0)
+    }
+}
+
+/// Error returned when loading or saving model data fails
+#[derive(Debug)]
+pub enum ModelError {
+    /// An I/O error occurred
+    Io(std::io::Error),
+    /// A line in a word count file could not be parsed
+    Parse {
+        /// The 1-based line number
+        line: usize,
+        /// Description of the problem
+        reason: &'static str,
+        /// The file containing the line, if the data was read from a file
+        path: Option<std::path::PathBuf>,
+    },
+    /// The data does not start with the model file magic number
+    BadMagic,
+    /// The model was written in a format version this release does not support
+    UnsupportedVersion {
+        /// The version of the model data
+        found: u16,
+        /// The latest version supported by this release
+        supported: u16,
+    },
+    /// The checksum of the model data does not match the stored checksum
+    ChecksumMismatch {
+        /// The checksum computed from the data
+        expected: u32,
+        /// The checksum stored in the data
+        found: u32,
+    },
+    /// The model data is malformed
+    Corrupt(&'static str),
+    /// The data is compressed in a format for which the crate feature is not enabled
+    UnsupportedCompression(&'static str),
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Parse { line, reason, path } => {
+                write!(f, "parse error at line {line}")?;
+                if let Some(path) = path {
+                    write!(f, " of {}", path.display())?;
+                }
+                write!(f, ": {reason}")
+            }
+            Self::BadMagic => f.write_str("not an instant-segment model"),
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported model format version {found} (latest supported version is {supported})"
+            ),
+            Self::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch (expected {expected:#010x}, found {found:#010x})"
+            ),
+            Self::Corrupt(reason) => write!(f, "corrupt model data: {reason}"),
+            Self::UnsupportedCompression(format) => write!(
+                f,
+                "{format} compressed data requires the `{format}` feature"
+            ),
+        }
+    }
+}
+
+impl ModelError {
+    /// Attach the `path` of the file being read to parse errors
+    pub(crate) fn in_file(self, path: &std::path::Path) -> Self {
+        match self {
+            Self::Parse { line, reason, .. } => Self::Parse {
+                line,
+                reason,
+                path: Some(path.to_owned()),
+            },
+            e => e,
+        }
+    }
+}
+
+impl From<std::io::Error> for ModelError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Parameters for scoring words
+///
+/// The defaults match the scoring used by earlier releases. See [`Segmenter::tune()`] for finding
+/// better values for a given model.
+#[cfg_attr(feature = "with-serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "with-serde", serde(try_from = "UncheckedParams"))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    /// Offset for the score of unknown words, which must be finite
+    ///
+    /// Unknown words are scored as `(unknown_base - log10(total) - len) * len / unknown_word_len`,
+    /// where `total` is the total unigram count and `len` the length of the word.
+    pub unknown_base: f64,
+    /// The typical word length, used to estimate how many words an unknown word replaces
+    ///
+    /// Must be finite and positive.
+    pub unknown_word_len: f64,
+    /// Weight of the bigram probability when interpolating it with the unigram probability
+    ///
+    /// Must be between `0.0` and `1.0`. With a weight of `1.0`, the bigram probability is used as
+    /// is whenever it's available.
+    pub bigram_weight: f64,
+}
+
+impl Params {
+    pub(crate) fn validate(&self) -> Result<(), InvalidParams> {
+        if !self.unknown_base.is_finite() {
+            Err(InvalidParams("unknown_base must be finite"))
+        } else if !(self.unknown_word_len.is_finite() && self.unknown_word_len > 0.0) {
+            Err(InvalidParams(
+                "unknown_word_len must be finite and positive",
+            ))
+        } else if !(0.0..=1.0).contains(&self.bigram_weight) {
+            Err(InvalidParams("bigram_weight must be between 0 and 1"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// [`Params`] as deserialized, before they are validated
+#[cfg(feature = "with-serde")]
+#[derive(Deserialize)]
+struct UncheckedParams {
+    unknown_base: f64,
+    unknown_word_len: f64,
+    bigram_weight: f64,
+}
+
+#[cfg(feature = "with-serde")]
+impl std::convert::TryFrom<UncheckedParams> for Params {
+    type Error = InvalidParams;
+
+    fn try_from(unchecked: UncheckedParams) -> Result<Self, Self::Error> {
+        let params = Self {
+            unknown_base: unchecked.unknown_base,
+            unknown_word_len: unchecked.unknown_word_len,
+            bigram_weight: unchecked.bigram_weight,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            unknown_base: 1.0,
+            unknown_word_len: 5.0,
+            bigram_weight: 1.0,
+        }
     }
 }
 
@@ -310,9 +855,154 @@ const DEFAULT_LIMIT: usize = 24;
 pub mod tests {
     use super::*;
 
+    pub(crate) use crate::test_cases::choose_spain;
+
+    pub(crate) fn segment(segmenter: &Segmenter, input: &str) -> Vec<std::string::String> {
+        let mut search = Search::default();
+        let segments = segmenter.segment(input, &mut search).unwrap();
+        segments.map(|word| word.to_owned()).collect()
+    }
+
+    pub(crate) fn segmenter(unigrams: &[(&str, f64)], bigrams: &[(&str, &str, f64)]) -> Segmenter {
+        Segmenter::new(
+            unigrams.iter().map(|&(word, count)| (word.into(), count)),
+            bigrams
+                .iter()
+                .map(|&(first, second, count)| ((first.into(), second.into()), count)),
+        )
+    }
+
+    #[test]
+    fn test_segment_detailed() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let result = segmenter
+            .segment_detailed("choosespainx", &mut search)
+            .unwrap();
+        assert_eq!(result.words(), ["choose", "spain", "x"]);
+        assert_eq!(result.spans(), [0..6, 6..11, 11..12]);
+
+        let details = result.details().unwrap();
+        let sources = details.iter().map(|d| d.source).collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            [WordSource::Unigram, WordSource::Bigram, WordSource::Unknown]
+        );
+        let total = details.iter().map(|d| d.score).sum::<f64>();
+        assert!((total - result.score()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_segment_detailed_edges() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let empty = segmenter.segment_detailed("", &mut search).unwrap();
+        assert!(empty.words().is_empty() && empty.spans().is_empty());
+
+        let single = segmenter.segment_detailed("x", &mut search).unwrap();
+        assert_eq!(single.words(), ["x"]);
+        let details = single.details().unwrap();
+        assert_eq!(details[0].source, WordSource::Unknown);
+        assert_eq!(details[0].score, single.score());
+
+        assert!(segmenter.segment_detailed("Spain", &mut search).is_err());
+    }
+
+    #[test]
+    fn test_set_params() {
+        let mut segmenter = Segmenter::new([("choose".into(), 80_000.0)], []);
+        for params in [
+            Params {
+                unknown_base: f64::NAN,
+                ..Params::default()
+            },
+            Params {
+                unknown_word_len: 0.0,
+                ..Params::default()
+            },
+            Params {
+                bigram_weight: -0.5,
+                ..Params::default()
+            },
+        ] {
+            assert!(segmenter.set_params(params).is_err());
+        }
+        assert_eq!(segmenter.params(), &Params::default());
+
+        let params = Params {
+            bigram_weight: 0.5,
+            ..Params::default()
+        };
+        segmenter.set_params(params).unwrap();
+        assert_eq!(segmenter.params(), &params);
+    }
+
+    #[test]
+    fn test_zero_bigram_weight() {
+        let mut segmenter = choose_spain();
+        segmenter
+            .set_params(Params {
+                bigram_weight: 0.0,
+                ..Params::default()
+            })
+            .unwrap();
+
+        let mut search = Search::default();
+        let result = segmenter
+            .segment_detailed("choosespain", &mut search)
+            .unwrap();
+        let details = result.details().unwrap();
+        assert!(details.iter().all(|d| d.source == WordSource::Unigram));
+        assert_eq!(
+            segmenter.score("spain", Some("choose")),
+            segmenter.score("spain", None)
+        );
+    }
+
+    #[cfg(feature = "with-serde")]
+    #[test]
+    fn test_deserialize_params() {
+        use serde::de::value::{Error, MapDeserializer};
+
+        let deserialize = |bigram_weight: f64| {
+            let fields = [
+                ("unknown_base", 1.0),
+                ("unknown_word_len", 5.0),
+                ("bigram_weight", bigram_weight),
+            ];
+            Params::deserialize(MapDeserializer::<_, Error>::new(IntoIterator::into_iter(
+                fields,
+            )))
+        };
+
+        assert_eq!(deserialize(1.0).unwrap(), Params::default());
+        let err = deserialize(f64::NAN).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid scoring parameters: bigram_weight must be between 0 and 1"
+        );
+    }
+
     #[test]
     fn test_clean() {
-        Ascii::new("Can't buy me love!").unwrap_err();
+        let err = Ascii::new("Can't buy me love!").unwrap_err();
+        assert_eq!((err.position(), err.character()), (0, 'C'));
+        let err = Ascii::new("caf\u{e9}").unwrap_err();
+        assert_eq!((err.position(), err.character()), (3, '\u{e9}'));
+        assert_eq!(err.to_string(), "invalid character '\u{e9}' at byte 3");
+
+        let input = "Caf\u{e9} 42!";
+        let all = InvalidCharacter::all(input)
+            .iter()
+            .map(|err| (err.position(), err.character()))
+            .collect::<Vec<_>>();
+        assert_eq!(all, [(0, 'C'), (3, '\u{e9}'), (5, ' '), (8, '!')]);
+        assert_eq!(
+            Ascii::new(input).unwrap_err(),
+            InvalidCharacter::all(input)[0]
+        );
+        assert_eq!(clean(input), "caf42");
+        assert!(InvalidCharacter::all(&clean(input)).is_empty());
         let text = Ascii::new("cantbuymelove").unwrap();
         assert_eq!(&text[0..text.len()], "cantbuymelove");
         let text_with_numbers = Ascii::new("c4ntbuym3l0v3").unwrap();
@@ -0,0 +1,385 @@
+use std::fmt;
+
+use smartstring::alias::String;
+
+use crate::{
+    Ascii, HashMap, InvalidCharacter, Params, Search, SegmentState, Segmenter, Segments,
+    DEFAULT_LIMIT,
+};
+
+impl Segmenter {
+    /// Create a `Segmenter` from the summed counts of the given `models`
+    ///
+    /// Each model contributes in proportion to the total count it was built from, so the unigram
+    /// frequencies are those of the model that would have been built from the combined counts.
+    /// Since the model doesn't keep the bigram totals, bigram frequencies are an approximation:
+    /// they are weighted by the unigram totals of only the models that have bigrams, so models
+    /// without bigrams don't lower the frequencies of the others.
+    ///
+    /// The result uses the largest word length limit of the `models` and the default
+    /// [`Params`], since parameters tuned for one of the models don't carry over.
+    pub fn merge<'a>(models: impl IntoIterator<Item = &'a Segmenter>) -> Self {
+        let mut scores = HashMap::<String, (f64, HashMap<String, f64>)>::default();
+        let mut uni_total = 0.0;
+        let mut bi_total = 0.0;
+        let mut limit = None;
+        for model in models {
+            let total = model.total_count();
+            for (word, (uni, bi_scores)) in &model.scores {
+                let (merged_uni, merged_bi) = scores.entry(word.clone()).or_default();
+                *merged_uni += 10f64.powf(*uni) * total;
+                for (prev, bi) in bi_scores {
+                    *merged_bi.entry(prev.clone()).or_default() += 10f64.powf(*bi) * total;
+                }
+            }
+
+            uni_total += total;
+            if model.bigram_len() > 0 {
+                bi_total += total;
+            }
+            limit = limit.max(Some(model.limit));
+        }
+
+        for (uni, bi_scores) in scores.values_mut() {
+            *uni = (*uni / uni_total).log10();
+            for bi in bi_scores.values_mut() {
+                *bi = (*bi / bi_total).log10();
+            }
+        }
+
+        Self {
+            scores,
+            uni_total_log10: uni_total.log10(),
+            limit: limit.unwrap_or(DEFAULT_LIMIT),
+            params: Params::default(),
+        }
+    }
+}
+
+/// Several [`Segmenter`]s combined at query time
+///
+/// Every word is scored against each of the models, and the scores are combined according to the
+/// [`Interpolation`]. Unlike [`Segmenter::merge()`], this keeps the models separate, so the
+/// weights can be changed without rebuilding a model.
+pub struct Mixture {
+    models: Vec<(Segmenter, f64)>,
+    interpolation: Interpolation,
+    limit: usize,
+}
+
+impl Mixture {
+    /// Combine the `models` with the given weights
+    ///
+    /// The weights are normalized to sum to one. The word length limit is the largest limit of
+    /// the `models`.
+    ///
+    /// Returns an error if any of the weights is negative or not finite, or if they add up to
+    /// zero, which includes the case where there are no `models`.
+    pub fn new(
+        models: Vec<(Segmenter, f64)>,
+        interpolation: Interpolation,
+    ) -> Result<Self, InvalidWeights> {
+        let total = total_weight(models.iter().map(|&(_, weight)| weight))?;
+        let limit = models
+            .iter()
+            .map(|(model, _)| model.limit)
+            .max()
+            .unwrap_or(DEFAULT_LIMIT);
+
+        let mut mixture = Self {
+            models,
+            interpolation,
+            limit,
+        };
+        mixture.normalize(total);
+        Ok(mixture)
+    }
+
+    /// Segment the text in `input`
+    ///
+    /// Like [`Segmenter::segment()`], but scoring words against the mixture.
+    pub fn segment<'a>(
+        &self,
+        input: &str,
+        search: &'a mut Search,
+    ) -> Result<Segments<'a>, InvalidCharacter> {
+        let mut state = SegmentState::new(Ascii::new(input)?, self, search);
+        let score = match input {
+            "" => 0.0,
+            _ => state.run(),
+        };
+
+        Ok(Segments {
+            iter: search.result.iter(),
+            score,
+            pruned: false,
+        })
+    }
+
+    /// Returns the sentence's score against the mixture, see [`Segmenter::score_sentence()`]
+    pub fn score_sentence<'a>(&self, mut words: impl Iterator<Item = &'a str>) -> Option<f64> {
+        let mut prev = words.next()?;
+        let mut score = self.score(prev, None);
+        for word in words {
+            score += self.score(word, Some(prev));
+            prev = word;
+        }
+        Some(score)
+    }
+
+    pub(crate) fn score(&self, word: &str, previous: Option<&str>) -> f64 {
+        let scores = self
+            .models
+            .iter()
+            .map(|(model, weight)| (model.score(word, previous), *weight));
+
+        match self.interpolation {
+            Interpolation::Linear => {
+                // Sum the probabilities relative to the largest one, to avoid underflow
+                let scores = scores.collect::<Vec<_>>();
+                let max = scores
+                    .iter()
+                    .map(|&(score, _)| score)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let sum = scores
+                    .iter()
+                    .map(|&(score, weight)| weight * 10f64.powf(score - max))
+                    .sum::<f64>();
+                max + sum.log10()
+            }
+            Interpolation::LogLinear => scores.map(|(score, weight)| weight * score).sum(),
+        }
+    }
+
+    /// The models with their normalized weights
+    pub fn models(&self) -> &[(Segmenter, f64)] {
+        &self.models
+    }
+
+    /// Change the weight of the model at `index`, renormalizing the weights
+    ///
+    /// Returns an error, leaving the weights unchanged, if `weight` is negative or not finite, or
+    /// if the weights would add up to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_weight(&mut self, index: usize, weight: f64) -> Result<(), InvalidWeights> {
+        let weights = (self.models.iter().enumerate())
+            .map(|(i, &(_, cur))| if i == index { weight } else { cur });
+
+        let total = total_weight(weights)?;
+        self.models[index].1 = weight;
+        self.normalize(total);
+        Ok(())
+    }
+
+    /// The way scores are combined
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Customize the way scores are combined
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// The word length limit
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Customize the word length `limit`
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    fn normalize(&mut self, total: f64) {
+        for (_, weight) in &mut self.models {
+            *weight /= total;
+        }
+    }
+}
+
+/// The sum of the `weights`, if they are valid for a [`Mixture`]
+fn total_weight(weights: impl Iterator<Item = f64>) -> Result<f64, InvalidWeights> {
+    let mut total = 0.0;
+    for weight in weights {
+        if !(weight.is_finite() && weight >= 0.0) {
+            return Err(InvalidWeights("weights must be finite and non-negative"));
+        }
+        total += weight;
+    }
+
+    match total.is_finite() && total > 0.0 {
+        true => Ok(total),
+        false => Err(InvalidWeights("weights must add up to a positive number")),
+    }
+}
+
+/// Error returned for [`Mixture`] weights that are out of range
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidWeights(&'static str);
+
+impl std::error::Error for InvalidWeights {}
+
+impl fmt::Display for InvalidWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid mixture weights: {}", self.0)
+    }
+}
+
+/// How a [`Mixture`] combines the scores of its models
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Interpolation {
+    /// Weighted sum of the probabilities
+    ///
+    /// A word is likely if it's likely in any of the models, so this works well for adding
+    /// domain-specific vocabulary to a general model.
+    Linear,
+    /// Weighted sum of the scores, which are log probabilities
+    ///
+    /// A word is only likely if it's likely in all of the models.
+    LogLinear,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{choose_spain, segmenter};
+
+    fn segment(mixture: &Mixture, input: &str) -> Vec<std::string::String> {
+        let mut search = Search::default();
+        let segments = mixture.segment(input, &mut search).unwrap();
+        segments.map(|word| word.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_merge() {
+        let general = segmenter(
+            &[
+                ("choose", 80_000.0),
+                ("spain", 20_000.0),
+                ("pain", 90_000.0),
+            ],
+            &[("choose", "spain", 7.0)],
+        );
+        let domains = segmenter(&[("chooses", 5_000.0), ("pain", 5_000.0)], &[]);
+
+        let merged = Segmenter::merge([&general, &domains]);
+        let expected = segmenter(
+            &[
+                ("choose", 80_000.0),
+                ("chooses", 5_000.0),
+                ("spain", 20_000.0),
+                ("pain", 95_000.0),
+            ],
+            &[],
+        );
+
+        assert_eq!(merged.vocab_len(), 4);
+        assert!((merged.total_count() - 200_000.0).abs() < 1e-6);
+        for word in ["choose", "chooses", "spain", "pain"] {
+            let (a, b) = (
+                merged.unigram_log_prob(word),
+                expected.unigram_log_prob(word),
+            );
+            assert!((a.unwrap() - b.unwrap()).abs() < 1e-9);
+        }
+
+        // Models without bigrams leave the bigram frequencies of the others unchanged
+        let bi = merged.bigram_log_prob("choose", "spain").unwrap();
+        let general_bi = general.bigram_log_prob("choose", "spain").unwrap();
+        assert!((bi - general_bi).abs() < 1e-9);
+
+        // Bigram frequencies are weighted by the unigram totals of the models with bigrams
+        let other = segmenter(
+            &[("choose", 1.0), ("pain", 3.0)],
+            &[("choose", "pain", 1.0)],
+        );
+        let merged = Segmenter::merge([&general, &domains, &other]);
+        let weight = general.total_count() / (general.total_count() + other.total_count());
+        let bi = merged.bigram_log_prob("choose", "spain").unwrap();
+        assert!((bi - weight.log10()).abs() < 1e-9);
+        let bi = merged.bigram_log_prob("choose", "pain").unwrap();
+        assert!((bi - (1.0 - weight).log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mixture() {
+        let general = segmenter(
+            &[
+                ("choose", 80_000.0),
+                ("spain", 20_000.0),
+                ("pain", 90_000.0),
+            ],
+            &[("choose", "spain", 7.0)],
+        );
+        let domains = segmenter(&[("xyzzy", 10.0), ("spain", 10.0)], &[]);
+
+        let models = vec![(general, 3.0), (domains, 1.0)];
+        let mut mixture = Mixture::new(models, Interpolation::Linear).unwrap();
+        assert_eq!(mixture.models()[0].1, 0.75);
+        assert_eq!(segment(&mixture, "choosespain"), ["choose", "spain"]);
+        assert_eq!(segment(&mixture, "choosexyzzy"), ["choose", "xyzzy"]);
+
+        let mut search = Search::default();
+        let score = mixture.segment("choosespain", &mut search).unwrap().score();
+        let expected = mixture.score_sentence(["choose", "spain"].iter().copied());
+        assert!((score - expected.unwrap()).abs() < 1e-9);
+
+        // With a single model, the mixture scores like the model itself
+        mixture.set_weight(1, 0.0).unwrap();
+        let general = &mixture.models()[0].0;
+        let expected = general.score_sentence(["choose", "spain"].iter().copied());
+        let score = mixture.score_sentence(["choose", "spain"].iter().copied());
+        assert!((score.unwrap() - expected.unwrap()).abs() < 1e-9);
+
+        mixture.set_interpolation(Interpolation::LogLinear);
+        let score = mixture.score_sentence(["choose", "spain"].iter().copied());
+        assert!((score.unwrap() - expected.unwrap()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mixture_weights() {
+        for weights in [
+            [1.0, -1.0],
+            [f64::NAN, 1.0],
+            [f64::INFINITY, 1.0],
+            [0.0, 0.0],
+        ] {
+            let models = weights.iter().map(|&weight| (choose_spain(), weight));
+            assert!(Mixture::new(models.collect(), Interpolation::Linear).is_err());
+        }
+        assert!(Mixture::new(Vec::new(), Interpolation::Linear).is_err());
+
+        // The sum of the weights overflows
+        let models = vec![(choose_spain(), f64::MAX), (choose_spain(), f64::MAX)];
+        assert!(Mixture::new(models, Interpolation::Linear).is_err());
+
+        let models = vec![(choose_spain(), 1.0), (choose_spain(), 0.0)];
+        let mut mixture = Mixture::new(models, Interpolation::LogLinear).unwrap();
+        for weight in [-1.0, f64::NAN] {
+            assert!(mixture.set_weight(1, weight).is_err());
+        }
+        let err = mixture.set_weight(0, 0.0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid mixture weights: weights must add up to a positive number"
+        );
+        assert_eq!(mixture.models()[0].1, 1.0);
+        assert_eq!(mixture.models()[1].1, 0.0);
+
+        mixture.set_weight(1, 3.0).unwrap();
+        assert_eq!(mixture.models()[0].1, 0.25);
+        assert_eq!(mixture.models()[1].1, 0.75);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_weight_index() {
+        let models = vec![(choose_spain(), 1.0)];
+        let mut mixture = Mixture::new(models, Interpolation::Linear).unwrap();
+        let _ = mixture.set_weight(1, 1.0);
+    }
+}
@@ -0,0 +1,723 @@
+//! Versioned binary model format
+//!
+//! A model file starts with an 8-byte magic number and a format version, followed by the
+//! [`Metadata`] and some statistics about the model, the word scores, and a CRC-32 checksum of
+//! everything before it. All integers and floats are stored in little-endian byte order, strings
+//! are stored as their length (a `u32`) followed by their UTF-8 bytes.
+//!
+//! Unlike serializing a [`Segmenter`] with serde, this format does not depend on the in-memory
+//! representation of the model, so files remain readable across releases. Files written in an
+//! unsupported format version result in [`ModelError::UnsupportedVersion`].
+//!
+//! The scoring [`Params`] are stored after the word length limit, followed by a byte selecting
+//! the encoding of the word scores: `0` for the full encoding written by [`Segmenter::save()`],
+//! `1` for the compact encoding written by [`Segmenter::save_compact()`].
+//!
+//! The compact encoding is meant for distributing models, for example to web browsers. Words
+//! are stored in sorted order as the length of the prefix they share with the previous word
+//! followed by the rest of the word, scores are quantized to 16 bits, and bigrams refer to their
+//! first word by its index in the vocabulary. Lengths and indexes are stored as LEB128 varints.
+//!
+//! Models can be compressed by saving them through a [`compression::Encoder`]; compressed models
+//! are detected automatically when loading.
+
+use std::convert::TryFrom;
+use std::io::{self, BufReader, Read, Write};
+
+use smartstring::alias::String;
+
+use crate::{compression, HashMap, ModelError, Params, Segmenter};
+
+/// Magic number at the start of every model file
+pub const MAGIC: [u8; 8] = *b"\x89ISM\r\n\x1a\n";
+
+/// The format version written by [`Segmenter::save()`]
+pub const VERSION: u16 = 1;
+
+impl Segmenter {
+    /// Write the model to `writer` in the versioned binary format
+    ///
+    /// Words are written in sorted order, so saving the same model twice produces identical files.
+    pub fn save(&self, writer: impl Write, metadata: &Metadata) -> Result<(), ModelError> {
+        self.write(writer, metadata, FULL)
+    }
+
+    /// Write the model to `writer` in the compact encoding of the binary format
+    ///
+    /// Compact models are typically less than a third of the size of models written by
+    /// [`Segmenter::save()`]. Scores are quantized to 16 bits, which changes the word
+    /// probabilities by a small fraction of a percent and rarely affects segmentation. Bigrams
+    /// whose first word is not in the vocabulary are never used for scoring, and are left out.
+    pub fn save_compact(&self, writer: impl Write, metadata: &Metadata) -> Result<(), ModelError> {
+        self.write(writer, metadata, COMPACT)
+    }
+
+    fn write(
+        &self,
+        writer: impl Write,
+        metadata: &Metadata,
+        encoding: u8,
+    ) -> Result<(), ModelError> {
+        let mut words = self.scores.iter().collect::<Vec<_>>();
+        words.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let bigrams = match encoding {
+            COMPACT => self
+                .scores
+                .values()
+                .flat_map(|(_, bi_scores)| bi_scores.keys())
+                .filter(|prev| self.scores.contains_key(*prev))
+                .count(),
+            _ => self.bigram_len(),
+        };
+
+        let mut writer = Checksummed::new(writer);
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        write_str(&mut writer, &metadata.source)?;
+        write_str(&mut writer, &metadata.build_date)?;
+        write_str(&mut writer, &metadata.smoothing)?;
+        write_u64(&mut writer, self.vocab_len() as u64)?;
+        write_u64(&mut writer, bigrams as u64)?;
+        write_u64(&mut writer, self.limit as u64)?;
+        for param in [
+            self.params.unknown_base,
+            self.params.unknown_word_len,
+            self.params.bigram_weight,
+        ] {
+            writer.write_all(&param.to_le_bytes())?;
+        }
+        writer.write_all(&[encoding])?;
+
+        writer.write_all(&self.uni_total_log10.to_le_bytes())?;
+        match encoding {
+            COMPACT => write_compact(&mut writer, &words)?,
+            _ => write_full(&mut writer, &words)?,
+        }
+
+        let checksum = writer.hasher.clone().finalize();
+        let mut writer = writer.inner;
+        writer.write_all(&checksum.to_le_bytes())?;
+        Ok(writer.flush()?)
+    }
+
+    /// Read a model written by [`Segmenter::save()`] from `reader`
+    pub fn load(reader: impl Read) -> Result<Self, ModelError> {
+        Self::load_with_info(reader).map(|(segmenter, _)| segmenter)
+    }
+
+    /// Read a model written by [`Segmenter::save()`], along with information about it
+    ///
+    /// The checksum is verified after the whole model has been read.
+    pub fn load_with_info(reader: impl Read) -> Result<(Self, ModelInfo), ModelError> {
+        let reader = compression::decode(BufReader::new(reader))?;
+        let mut reader = Checksummed::new(reader);
+        let info = read_header(&mut reader)?;
+
+        let uni_total_log10 = read_f64(&mut reader)?;
+        let scores = match info.compact {
+            true => read_compact(&mut reader, info.vocabulary_size)?,
+            false => read_full(&mut reader, info.vocabulary_size)?,
+        };
+
+        let expected = reader.hasher.clone().finalize();
+        let mut found = [0; 4];
+        read_exact(&mut reader.inner, &mut found)?;
+        let found = u32::from_le_bytes(found);
+        if found != expected {
+            return Err(ModelError::ChecksumMismatch { expected, found });
+        }
+
+        let segmenter = Self {
+            scores,
+            uni_total_log10,
+            limit: info.limit,
+            params: info.params,
+        };
+        Ok((segmenter, info))
+    }
+}
+
+/// Read the header of a model file written by [`Segmenter::save()`]
+///
+/// Only the start of the file is read, so the checksum is not verified.
+pub fn read_info(reader: impl Read) -> Result<ModelInfo, ModelError> {
+    read_header(compression::decode(BufReader::new(reader))?)
+}
+
+fn read_header(mut reader: impl Read) -> Result<ModelInfo, ModelError> {
+    let mut magic = [0; 8];
+    read_exact(&mut reader, &mut magic)?;
+    if magic != MAGIC {
+        return Err(ModelError::BadMagic);
+    }
+
+    let mut version = [0; 2];
+    read_exact(&mut reader, &mut version)?;
+    let version = u16::from_le_bytes(version);
+    if version != VERSION {
+        return Err(ModelError::UnsupportedVersion {
+            found: version,
+            supported: VERSION,
+        });
+    }
+
+    let metadata = Metadata {
+        source: read_str(&mut reader)?.into(),
+        build_date: read_str(&mut reader)?.into(),
+        smoothing: read_str(&mut reader)?.into(),
+    };
+
+    let vocabulary_size = read_u64(&mut reader)?;
+    let bigrams = read_u64(&mut reader)?;
+    // A limit beyond the address space (written on a 64-bit target) is as good as unlimited
+    let limit = usize::try_from(read_raw_u64(&mut reader)?).unwrap_or(usize::MAX);
+    if limit == 0 {
+        return Err(ModelError::Corrupt("invalid word length limit"));
+    }
+
+    let params = Params {
+        unknown_base: read_f64(&mut reader)?,
+        unknown_word_len: read_f64(&mut reader)?,
+        bigram_weight: read_f64(&mut reader)?,
+    };
+
+    if params.validate().is_err() {
+        return Err(ModelError::Corrupt("invalid scoring parameters"));
+    }
+
+    let mut encoding = [0];
+    read_exact(&mut reader, &mut encoding)?;
+    let compact = match encoding[0] {
+        FULL => false,
+        COMPACT => true,
+        _ => return Err(ModelError::Corrupt("unknown score encoding")),
+    };
+
+    Ok(ModelInfo {
+        version,
+        metadata,
+        vocabulary_size,
+        bigrams,
+        limit,
+        params,
+        compact,
+    })
+}
+
+type Scores = HashMap<String, Entry>;
+type Entry = (f64, HashMap<String, f64>);
+
+fn write_full(writer: &mut impl Write, words: &[(&String, &Entry)]) -> Result<(), ModelError> {
+    for (word, (uni, bi_scores)) in words {
+        write_str(writer, word)?;
+        writer.write_all(&uni.to_le_bytes())?;
+        write_u32(writer, bi_scores.len())?;
+        let mut bi_scores = bi_scores.iter().collect::<Vec<_>>();
+        bi_scores.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (prev, bi) in bi_scores {
+            write_str(writer, prev)?;
+            writer.write_all(&bi.to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_full(reader: &mut impl Read, vocabulary_size: usize) -> Result<Scores, ModelError> {
+    // Don't trust the size from the header for allocations before verifying the checksum
+    let mut scores = HashMap::default();
+    scores.reserve(vocabulary_size.min(1 << 20));
+    for _ in 0..vocabulary_size {
+        let word = read_str(reader)?;
+        let uni = read_f64(reader)?;
+        let len = read_u32(reader)?;
+        let mut bi_scores = HashMap::default();
+        for _ in 0..len {
+            let prev = read_str(reader)?;
+            bi_scores.insert(prev, read_f64(reader)?);
+        }
+        scores.insert(word, (uni, bi_scores));
+    }
+
+    Ok(scores)
+}
+
+fn write_compact(writer: &mut impl Write, words: &[(&String, &Entry)]) -> Result<(), ModelError> {
+    let index = words
+        .iter()
+        .enumerate()
+        .map(|(i, (word, _))| (word.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let bigrams = words
+        .iter()
+        .map(|(_, (_, bi_scores))| {
+            let mut bi_scores = bi_scores
+                .iter()
+                .filter_map(|(prev, bi)| Some((*index.get(prev.as_str())?, *bi)))
+                .collect::<Vec<_>>();
+            bi_scores.sort_unstable_by_key(|&(i, _)| i);
+            bi_scores
+        })
+        .collect::<Vec<_>>();
+
+    let uni_min = min_score(words.iter().map(|(_, (uni, _))| *uni));
+    let bi_min = min_score(bigrams.iter().flatten().map(|&(_, bi)| bi));
+    writer.write_all(&uni_min.to_le_bytes())?;
+    writer.write_all(&bi_min.to_le_bytes())?;
+
+    let mut last = "";
+    for (word, (uni, _)) in words {
+        let shared = last
+            .bytes()
+            .zip(word.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        write_varint(writer, shared)?;
+        write_varint(writer, word.len() - shared)?;
+        writer.write_all(&word.as_bytes()[shared..])?;
+        writer.write_all(&quantize(*uni, uni_min).to_le_bytes())?;
+        last = word;
+    }
+
+    for bi_scores in bigrams {
+        write_varint(writer, bi_scores.len())?;
+        let mut last = 0;
+        for (i, bi) in bi_scores {
+            write_varint(writer, i - last)?;
+            writer.write_all(&quantize(bi, bi_min).to_le_bytes())?;
+            last = i;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_compact(reader: &mut impl Read, vocabulary_size: usize) -> Result<Scores, ModelError> {
+    let uni_min = read_f64(reader)?;
+    let bi_min = read_f64(reader)?;
+
+    // Don't trust the size from the header for allocations before verifying the checksum
+    let mut words = Vec::with_capacity(vocabulary_size.min(1 << 20));
+    let mut buf = Vec::new();
+    for _ in 0..vocabulary_size {
+        let shared = read_varint(reader)?;
+        if shared > buf.len() {
+            return Err(ModelError::Corrupt("invalid word prefix"));
+        }
+
+        buf.truncate(shared);
+        let len = read_varint(reader)?;
+        Read::take(&mut *reader, len as u64).read_to_end(&mut buf)?;
+        if buf.len() != shared + len {
+            return Err(ModelError::Corrupt("unexpected end of data"));
+        }
+
+        let word = std::str::from_utf8(&buf).map_err(|_| ModelError::Corrupt("invalid UTF-8"))?;
+        let uni = dequantize(read_u16(reader)?, uni_min);
+        words.push((String::from(word), uni));
+    }
+
+    let mut scores = HashMap::default();
+    scores.reserve(words.len());
+    for (word, uni) in &words {
+        let len = read_varint(reader)?;
+        let mut bi_scores = HashMap::default();
+        let mut last = 0usize;
+        for _ in 0..len {
+            let i = last
+                .checked_add(read_varint(reader)?)
+                .filter(|&i| i < words.len())
+                .ok_or(ModelError::Corrupt("bigram index out of range"))?;
+            let bi = dequantize(read_u16(reader)?, bi_min);
+            bi_scores.insert(words[i].0.clone(), bi);
+            last = i;
+        }
+        scores.insert(word.clone(), (*uni, bi_scores));
+    }
+
+    Ok(scores)
+}
+
+/// The lowest finite score, the lower end of the range for quantization
+fn min_score(scores: impl Iterator<Item = f64>) -> f64 {
+    scores.filter(|s| s.is_finite()).fold(0.0, f64::min)
+}
+
+/// Map `score` from the range `min..=0` to a `u16`
+fn quantize(score: f64, min: f64) -> u16 {
+    match min < 0.0 {
+        true => ((1.0 - score / min).clamp(0.0, 1.0) * f64::from(u16::MAX)).round() as u16,
+        false => 0,
+    }
+}
+
+fn dequantize(value: u16, min: f64) -> f64 {
+    min * (1.0 - f64::from(value) / f64::from(u16::MAX))
+}
+
+/// Descriptive information stored with a model
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// Description of the corpus the model was built from
+    pub source: std::string::String,
+    /// The date the model was built, for example `2026-10-18`
+    pub build_date: std::string::String,
+    /// Description of the smoothing applied to the counts
+    pub smoothing: std::string::String,
+}
+
+/// Information from the header of a model file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+    /// The format version the model was written in
+    pub version: u16,
+    /// The metadata passed to [`Segmenter::save()`]
+    pub metadata: Metadata,
+    /// The number of words in the model
+    pub vocabulary_size: usize,
+    /// The number of bigrams in the model
+    pub bigrams: usize,
+    /// The word length limit
+    pub limit: usize,
+    /// The scoring parameters
+    pub params: Params,
+    /// Whether the model was written in the compact encoding, see [`Segmenter::save_compact()`]
+    pub compact: bool,
+}
+
+/// Computes the CRC-32 checksum of all data read or written
+struct Checksummed<T> {
+    inner: T,
+    hasher: crc32fast::Hasher,
+}
+
+impl<T> Checksummed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> Result<(), ModelError> {
+    write_u32(writer, s.len())?;
+    Ok(writer.write_all(s.as_bytes())?)
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> Result<(), ModelError> {
+    let value = u32::try_from(value).map_err(|_| ModelError::Corrupt("length out of range"))?;
+    Ok(writer.write_all(&value.to_le_bytes())?)
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> Result<(), ModelError> {
+    Ok(writer.write_all(&value.to_le_bytes())?)
+}
+
+fn read_str(reader: &mut impl Read) -> Result<String, ModelError> {
+    let len = read_u32(reader)?;
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(ModelError::Corrupt("unexpected end of data"));
+    }
+
+    let s = std::str::from_utf8(&buf).map_err(|_| ModelError::Corrupt("invalid UTF-8"))?;
+    Ok(s.into())
+}
+
+fn write_varint(writer: &mut impl Write, mut value: usize) -> Result<(), ModelError> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        match value {
+            0 => return Ok(writer.write_all(&[byte])?),
+            _ => writer.write_all(&[byte | 0x80])?,
+        }
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<usize, ModelError> {
+    let mut value = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let mut byte = [0];
+        read_exact(reader, &mut byte)?;
+        let bits = usize::from(byte[0] & 0x7f);
+        value |= bits
+            .checked_shl(shift)
+            .filter(|shifted| shifted >> shift == bits)
+            .ok_or(ModelError::Corrupt("varint out of range"))?;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(ModelError::Corrupt("varint out of range"))
+}
+
+fn read_u16(reader: &mut impl Read) -> Result<u16, ModelError> {
+    let mut buf = [0; 2];
+    read_exact(reader, &mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<usize, ModelError> {
+    let mut buf = [0; 4];
+    read_exact(reader, &mut buf)?;
+    Ok(u32::from_le_bytes(buf) as usize)
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<usize, ModelError> {
+    usize::try_from(read_raw_u64(reader)?).map_err(|_| ModelError::Corrupt("size out of range"))
+}
+
+fn read_raw_u64(reader: &mut impl Read) -> Result<u64, ModelError> {
+    let mut buf = [0; 8];
+    read_exact(reader, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f64(reader: &mut impl Read) -> Result<f64, ModelError> {
+    let mut buf = [0; 8];
+    read_exact(reader, &mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+const FULL: u8 = 0;
+const COMPACT: u8 = 1;
+
+fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<(), ModelError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => ModelError::Corrupt("unexpected end of data"),
+        _ => ModelError::Io(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{self, choose_spain, segment};
+    use crate::Search;
+
+    #[test]
+    fn test_round_trip() {
+        let mut segmenter = choose_spain();
+        segmenter.set_limit(12);
+        let params = Params {
+            bigram_weight: 0.8,
+            ..Params::default()
+        };
+        segmenter.set_params(params).unwrap();
+
+        let metadata = Metadata {
+            source: "contrived".into(),
+            build_date: "2026-10-18".into(),
+            smoothing: "none".into(),
+        };
+        let mut buf = Vec::new();
+        segmenter.save(&mut buf, &metadata).unwrap();
+        assert_eq!(buf[..8], MAGIC);
+
+        let (loaded, info) = Segmenter::load_with_info(&buf[..]).unwrap();
+        assert_eq!(info, read_info(&buf[..]).unwrap());
+        assert_eq!(info.metadata, metadata);
+        assert_eq!((info.vocabulary_size, info.bigrams, info.limit), (4, 1, 12));
+        assert_eq!(info.params, params);
+
+        let mut search = Search::default();
+        let expected = segmenter.segment("choosespain", &mut search).unwrap();
+        let score = expected.score();
+        let segments = loaded.segment("choosespain", &mut search).unwrap();
+        assert_eq!(segments.score(), score);
+        assert_eq!(
+            segment(&loaded, "choosespain"),
+            segment(&segmenter, "choosespain")
+        );
+
+        let mut again = Vec::new();
+        loaded.save(&mut again, &metadata).unwrap();
+        assert_eq!(again, buf);
+    }
+
+    #[test]
+    fn test_empty() {
+        let segmenter = Segmenter::new([], []);
+        let mut buf = Vec::new();
+        segmenter.save(&mut buf, &Metadata::default()).unwrap();
+
+        let (loaded, info) = Segmenter::load_with_info(&buf[..]).unwrap();
+        assert_eq!((info.vocabulary_size, info.bigrams), (0, 0));
+        assert_eq!(info.metadata, Metadata::default());
+        assert_eq!(loaded.limit, segmenter.limit);
+    }
+
+    #[test]
+    fn test_errors() {
+        let segmenter = Segmenter::new([("spain".into(), 1.0)], []);
+        let mut buf = Vec::new();
+        segmenter.save(&mut buf, &Metadata::default()).unwrap();
+
+        let err = load_err(b"not a model");
+        assert!(matches!(err, ModelError::BadMagic));
+        assert!(matches!(load_err(b""), ModelError::Corrupt(_)));
+        assert!(matches!(load_err(&MAGIC[..4]), ModelError::Corrupt(_)));
+        assert!(matches!(load_err(&MAGIC), ModelError::Corrupt(_)));
+
+        // Any non-empty input would fail to segment with a limit of zero
+        let mut zero_limit = Segmenter::new([("spain".into(), 1.0)], []);
+        zero_limit.set_limit(0);
+        let mut zero_buf = Vec::new();
+        zero_limit
+            .save(&mut zero_buf, &Metadata::default())
+            .unwrap();
+        assert!(matches!(load_err(&zero_buf), ModelError::Corrupt(_)));
+        assert!(matches!(
+            read_info(&zero_buf[..]),
+            Err(ModelError::Corrupt(_))
+        ));
+
+        // The largest limit is kept (or clamped to `usize::MAX` on smaller targets)
+        let mut unlimited = Segmenter::new([("spain".into(), 1.0)], []);
+        unlimited.set_limit(usize::MAX);
+        let mut unlimited_buf = Vec::new();
+        unlimited
+            .save(&mut unlimited_buf, &Metadata::default())
+            .unwrap();
+        let loaded = Segmenter::load(&unlimited_buf[..]).unwrap();
+        assert_eq!(loaded.limit(), usize::MAX);
+        assert_eq!(read_info(&unlimited_buf[..]).unwrap().limit, usize::MAX);
+
+        let mut newer = buf.clone();
+        newer[8] = 99;
+        let err = load_err(&newer);
+        assert!(matches!(
+            err,
+            ModelError::UnsupportedVersion {
+                found: 99,
+                supported: VERSION
+            }
+        ));
+
+        let mut corrupted = buf.clone();
+        let last_score = corrupted.len() - 12;
+        corrupted[last_score] ^= 1;
+        let err = load_err(&corrupted);
+        assert!(matches!(err, ModelError::ChecksumMismatch { .. }));
+
+        let err = load_err(&buf[..buf.len() - 1]);
+        assert!(matches!(err, ModelError::Corrupt(_)));
+    }
+
+    #[test]
+    fn test_compact() {
+        // The bigram with an unknown first word is never used, and is left out
+        let mut segmenter = tests::segmenter(
+            &[
+                ("choose", 80_000.0),
+                ("chooses", 7_000.0),
+                ("spain", 20_000.0),
+                ("pain", 90_000.0),
+            ],
+            &[
+                ("choose", "spain", 7.0),
+                ("chooses", "pain", 3.0),
+                ("unknown", "pain", 1.0),
+            ],
+        );
+        segmenter.set_limit(12);
+
+        let (mut full, mut compact) = (Vec::new(), Vec::new());
+        segmenter.save(&mut full, &Metadata::default()).unwrap();
+        segmenter
+            .save_compact(&mut compact, &Metadata::default())
+            .unwrap();
+        assert!(compact.len() < full.len());
+
+        let (loaded, info) = Segmenter::load_with_info(&compact[..]).unwrap();
+        assert!(info.compact);
+        assert_eq!((info.vocabulary_size, info.bigrams, info.limit), (4, 2, 12));
+        assert_eq!(loaded.bigram_len(), 2);
+        for (word, uni) in segmenter.vocabulary() {
+            let loaded = loaded.unigram_log_prob(word).unwrap();
+            assert!((loaded - uni).abs() < 1e-4, "{} {}", loaded, uni);
+        }
+        let bi = loaded.bigram_log_prob("chooses", "pain").unwrap();
+        assert!((bi - segmenter.bigram_log_prob("chooses", "pain").unwrap()).abs() < 1e-4);
+
+        assert_eq!(segment(&loaded, "choosespain"), ["choose", "spain"]);
+
+        let mut again = Vec::new();
+        loaded
+            .save_compact(&mut again, &Metadata::default())
+            .unwrap();
+        assert_eq!(again, compact);
+
+        let mut truncated = compact[..compact.len() - 4].to_vec();
+        truncated.pop();
+        assert!(matches!(load_err(&truncated), ModelError::Corrupt(_)));
+    }
+
+    #[test]
+    fn test_compact_edges() {
+        // Words sharing all or none of their prefix with the previous word, and the extremes of
+        // the quantized score range
+        let segmenter = tests::segmenter(
+            &[("a", 1e12), ("aa", 1.0), ("aaa", 1.0), ("b", 1.0)],
+            &[("a", "a", 1.0)],
+        );
+        let mut buf = Vec::new();
+        segmenter
+            .save_compact(&mut buf, &Metadata::default())
+            .unwrap();
+
+        let loaded = Segmenter::load(&buf[..]).unwrap();
+        for (word, uni) in segmenter.vocabulary() {
+            let loaded = loaded.unigram_log_prob(word).unwrap();
+            assert!((loaded - uni).abs() < 1e-3, "{} {}", loaded, uni);
+        }
+        assert_eq!(loaded.bigram_len(), 1);
+
+        let empty = Segmenter::new([], []);
+        let mut buf = Vec::new();
+        empty.save_compact(&mut buf, &Metadata::default()).unwrap();
+        let (_, info) = Segmenter::load_with_info(&buf[..]).unwrap();
+        assert!(info.compact);
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 127, 128, 300, usize::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value).unwrap();
+            assert_eq!(read_varint(&mut &buf[..]).unwrap(), value);
+        }
+
+        let err = read_varint(&mut &[0xff; 11][..]).unwrap_err();
+        assert!(matches!(err, ModelError::Corrupt(_)));
+    }
+
+    fn load_err(data: &[u8]) -> ModelError {
+        Segmenter::load(data).err().unwrap()
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Ascii, InvalidCharacter, Path, Search, Segmentation, Segmenter};
+
+/// The maximum number of segmentations returned by [`Segmenter::segment_n_best()`]
+pub const MAX_N_BEST: usize = 1_000;
+
+impl Segmenter {
+    /// Find the `n` best segmentations of the text in `input`
+    ///
+    /// Returns up to `n` distinct segmentations, ordered from best to worst. Unlike
+    /// [`Segmenter::segment()`], which only keeps the best path to every position, this keeps
+    /// the `n` best paths to every position and scores each word against the previous word on
+    /// its own path. As a result, the best segmentation found here can in rare cases score
+    /// better than the one returned by [`Segmenter::segment()`].
+    ///
+    /// Time and memory grow linearly with `n`: every position in `input` keeps up to `n` paths,
+    /// and each of those is extended by every word that can follow it within the word length
+    /// limit. Values of `n` above [`MAX_N_BEST`] are clamped to it.
+    pub fn segment_n_best(
+        &self,
+        input: &str,
+        search: &mut Search,
+        n: usize,
+    ) -> Result<Vec<Segmentation>, InvalidCharacter> {
+        let text = Ascii::new(input)?;
+        let n = n.min(MAX_N_BEST);
+        search.clear();
+        if input.is_empty() || n == 0 {
+            return Ok(match n {
+                0 => Vec::new(),
+                _ => vec![Segmentation::default()],
+            });
+        }
+
+        // The best paths to `pos` are `paths[path_starts[pos]..path_starts[pos + 1]]`, ordered
+        // from best to worst
+        let (paths, path_starts) = (&mut search.paths, &mut search.path_starts);
+        paths.push(Path {
+            score: 0.0,
+            len: 0,
+            back: 0,
+        });
+        path_starts.extend_from_slice(&[0, 1]);
+
+        for end in 1..=text.len() {
+            let start = paths.len();
+            for split in end.saturating_sub(self.limit)..end {
+                let word = &text[split..end];
+                for back in path_starts[split]..path_starts[split + 1] {
+                    let path = paths[back];
+                    let prev = match path.len {
+                        0 => None,
+                        len => Some(&text[split - len..split]),
+                    };
+
+                    paths.push(Path {
+                        score: path.score + self.score(word, prev),
+                        len: end - split,
+                        back,
+                    });
+                }
+            }
+
+            // Candidates were added in order of their `back` index, so breaking ties on it
+            // resolves them in favor of earlier splits
+            paths[start..]
+                .sort_unstable_by(|a, b| b.score.total_cmp(&a.score).then(a.back.cmp(&b.back)));
+            paths.truncate(start.saturating_add(n));
+            path_starts.push(paths.len());
+        }
+
+        let last = path_starts[text.len()]..path_starts[text.len() + 1];
+        let results = paths[last]
+            .iter()
+            .map(|last| {
+                let (mut end, mut path) = (text.len(), last);
+                let mut spans = Vec::new();
+                while end > 0 {
+                    spans.push(end - path.len..end);
+                    end -= path.len;
+                    path = &paths[path.back];
+                }
+
+                spans.reverse();
+                Segmentation {
+                    words: spans.iter().map(|span| text[span.clone()].into()).collect(),
+                    spans,
+                    score: last.score,
+                    details: None,
+                    confidence: None,
+                }
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{choose_spain, segment};
+
+    #[test]
+    fn test_n_best() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+        let score = segmenter
+            .segment("choosespain", &mut search)
+            .unwrap()
+            .score();
+
+        let results = segmenter
+            .segment_n_best("choosespain", &mut search, 3)
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].words(), ["choose", "spain"]);
+        assert!((results[0].score() - score).abs() < 1e-9);
+        assert_eq!(results[1].words(), ["chooses", "pain"]);
+        assert_eq!(results[1].spans(), [0..7, 7..11]);
+        assert!(results[1].score() >= results[2].score());
+        assert_ne!(results[1].words(), results[2].words());
+
+        let best = segmenter
+            .segment_n_best("choosespain", &mut search, 1)
+            .unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].words(), segment(&segmenter, "choosespain"));
+
+        assert!(segmenter
+            .segment_n_best("choose Spain", &mut search, 3)
+            .is_err());
+    }
+
+    #[test]
+    fn test_n_best_limits() {
+        let segmenter = choose_spain();
+        let mut search = Search::default();
+
+        let none = segmenter.segment_n_best("choosespain", &mut search, 0);
+        assert!(none.unwrap().is_empty());
+        assert!(segmenter
+            .segment_n_best("", &mut search, 0)
+            .unwrap()
+            .is_empty());
+
+        let empty = segmenter.segment_n_best("", &mut search, 3).unwrap();
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].words().is_empty());
+
+        // Every one of the 2^9 ways to split 10 characters is returned exactly once
+        let all = segmenter
+            .segment_n_best("choosespai", &mut search, usize::MAX)
+            .unwrap();
+        assert_eq!(all.len(), 1 << 9);
+        assert!(all.windows(2).all(|w| w[0].score() >= w[1].score()));
+        let mut spans = all.iter().map(|s| s.spans().to_vec()).collect::<Vec<_>>();
+        spans.sort_by_key(|spans| spans.iter().map(|s| s.end).collect::<Vec<_>>());
+        spans.dedup();
+        assert_eq!(spans.len(), 1 << 9);
+
+        // The 2^10 ways to split 11 characters exceed the maximum
+        let clamped = segmenter
+            .segment_n_best("choosespain", &mut search, usize::MAX)
+            .unwrap();
+        assert_eq!(clamped.len(), MAX_N_BEST);
+    }
+}
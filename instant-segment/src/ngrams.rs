@@ -0,0 +1,407 @@
+//! Import word counts from the Google Books Ngram data
+//!
+//! Reads the unigram and bigram files from [version 3][ngrams] of the data set, which contain one
+//! ngram per line, followed by tab-separated `year,match_count,volume_count` triples. Input
+//! files are expected to be named like `eng-1-00000-00024.txt` (as downloaded by `data/grab.py`)
+//! or `eng-2-00000-of-00589.gz`; compressed files are decoded as described in
+//! [`crate::compression`].
+//!
+//! The counts for every input file are saved in a state directory, so an interrupted import
+//! resumes where it left off, and adding input files later only processes the new files.
+//!
+//! [ngrams]: https://storage.googleapis.com/books/ngrams/books/datasetsv3.html
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use smartstring::alias::String;
+
+use crate::counts::{for_each_line, open, read_unigrams, write_bigrams, write_unigrams};
+use crate::{HashMap, ModelError, Segmenter};
+
+/// Settings for importing the ngram data
+#[derive(Clone, Debug)]
+pub struct Import {
+    /// The language code used in the file names, like `eng` or `fre`
+    pub language: std::string::String,
+    /// The first year to count
+    pub start_year: u16,
+    /// The last year to count
+    pub end_year: u16,
+    /// Only count ngrams made up of these words, see [`read_word_list()`]
+    ///
+    /// The ngram data is noisy, so filtering with a word list is recommended. Words are matched
+    /// before lowercasing, so that `Spain` only matches the capitalized form in the word list.
+    pub word_list: Option<HashSet<String>>,
+    /// Keep at most this many of the most frequent unigrams
+    pub max_unigrams: Option<usize>,
+    /// Keep at most this many of the most frequent bigrams
+    pub max_bigrams: Option<usize>,
+}
+
+impl Import {
+    /// Import the ngram files in the `input` directory, keeping intermediate counts in `state`
+    ///
+    /// `progress` is called after each input file is processed, which happens in parallel if the
+    /// `rayon` feature is enabled. Returns an error if `state` was used for an import with
+    /// different language, years or word list; the output limits can be changed between runs.
+    pub fn run(
+        &self,
+        input: &Path,
+        state: &Path,
+        progress: impl Fn(&Part<'_>) + Sync,
+    ) -> Result<Ngrams, ModelError> {
+        fs::create_dir_all(state)?;
+        self.check_state(state)?;
+
+        let unigrams = self.import(1, input, state, &progress)?;
+        let unigrams = most_frequent(unigrams, self.max_unigrams)
+            .map(|(word, count)| (word, count as f64))
+            .collect();
+
+        let bigrams = self.import(2, input, state, &progress)?;
+        let bigrams = most_frequent(bigrams, self.max_bigrams)
+            .filter_map(|(words, count)| {
+                let (first, second) = words.split_once(' ')?;
+                Some(((first.into(), second.into()), count as f64))
+            })
+            .collect();
+
+        Ok(Ngrams { unigrams, bigrams })
+    }
+
+    /// Count the `n`-grams in all input files, using the saved counts where available
+    fn import(
+        &self,
+        n: usize,
+        input: &Path,
+        state: &Path,
+        progress: &(impl Fn(&Part<'_>) + Sync),
+    ) -> Result<HashMap<String, u64>, ModelError> {
+        let parts = find_parts(input, &self.language, n)?;
+        let saved = |path: &Path| {
+            let stem = path.file_stem().unwrap_or_default();
+            state.join(stem).with_extension("counts")
+        };
+
+        for_each_part(&parts, |path| {
+            let saved = saved(path);
+            if saved.is_file() {
+                progress(&Part { path, ngrams: None });
+                return Ok(());
+            }
+
+            let counts = self.count(path, n)?;
+            let tmp = saved.with_extension("tmp");
+            let writer = BufWriter::new(File::create(&tmp)?);
+            write_unigrams(writer, counts.iter().map(|(k, &v)| (k.as_str(), v as f64)))?;
+            fs::rename(&tmp, &saved)?;
+
+            let ngrams = Some(counts.len());
+            progress(&Part { path, ngrams });
+            Ok(())
+        })?;
+
+        let mut total = HashMap::<String, u64>::default();
+        for path in &parts {
+            let saved = saved(path);
+            let counts = read_unigrams(open(&saved)?).map_err(|e| e.in_file(&saved))?;
+            for (ngram, count) in counts {
+                *total.entry(ngram).or_default() += count as u64;
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Count the `n`-grams in the file at `path`
+    fn count(&self, path: &Path, n: usize) -> Result<HashMap<String, u64>, ModelError> {
+        let mut counts = HashMap::<String, u64>::default();
+        for_each_line(open(path)?, |ln| {
+            // Example line: `using pozzolan	1925,1,1	1947,2,2	1948,2,2	(...)`
+            let mut columns = ln.split('\t');
+            let ngram = match columns.next().and_then(|ngram| self.normalize(ngram, n)) {
+                Some(ngram) => ngram,
+                None => return Ok(()),
+            };
+
+            let mut matches = 0;
+            for column in columns {
+                let mut fields = column.split(',');
+                let year = fields.next().and_then(|year| year.parse::<u16>().ok());
+                let year = year.ok_or("invalid year")?;
+                let count = fields.next().and_then(|count| count.parse::<u64>().ok());
+                let count = count.ok_or("invalid match count")?;
+                if (self.start_year..=self.end_year).contains(&year) {
+                    matches += count;
+                }
+            }
+
+            if matches > 0 {
+                *counts.entry(ngram).or_default() += matches;
+            }
+            Ok(())
+        })
+        .map_err(|e| e.in_file(path))?;
+
+        Ok(counts)
+    }
+
+    /// Filter and lowercase the words in `ngram`, which must consist of `n` words
+    ///
+    /// Words with characters other than ASCII letters are rejected, as are words not in the word
+    /// list. Lowercasing happens last, so that the counts for `Spain` end up in `spain`.
+    fn normalize(&self, ngram: &str, n: usize) -> Option<String> {
+        let mut normalized = String::new();
+        let mut words = 0;
+        for word in ngram.trim().split(' ') {
+            let valid = !word.is_empty() && word.bytes().all(|b| b.is_ascii_alphabetic());
+            let listed = match &self.word_list {
+                Some(list) => list.contains(word),
+                None => true,
+            };
+            if !valid || !listed {
+                return None;
+            }
+
+            if words > 0 {
+                normalized.push(' ');
+            }
+            normalized.push_str(word);
+            words += 1;
+        }
+
+        normalized.make_ascii_lowercase();
+        (words == n).then_some(normalized)
+    }
+
+    /// Make sure the saved counts in `state` were created with the same settings
+    fn check_state(&self, state: &Path) -> Result<(), ModelError> {
+        let word_list = match &self.word_list {
+            Some(list) => {
+                let mut words = list.iter().collect::<Vec<_>>();
+                words.sort_unstable();
+                let mut hasher = crc32fast::Hasher::new();
+                for word in words {
+                    hasher.update(word.as_bytes());
+                    hasher.update(b"\n");
+                }
+                format!("{:08x}", hasher.finalize())
+            }
+            None => "none".to_owned(),
+        };
+
+        let settings = format!(
+            "language: {}\nyears: {}-{}\nword list: {}\n",
+            self.language, self.start_year, self.end_year, word_list
+        );
+
+        let path = state.join("settings");
+        match fs::read_to_string(&path) {
+            Ok(saved) if saved == settings => Ok(()),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "state directory contains counts for different settings",
+            )
+            .into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(fs::write(&path, settings)?),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Default for Import {
+    /// The settings used for the English data files shipped with this crate, without word list
+    fn default() -> Self {
+        Self {
+            language: "eng".to_owned(),
+            start_year: 2000,
+            end_year: u16::MAX,
+            word_list: None,
+            max_unigrams: Some(256 * 1024),
+            max_bigrams: Some(256 * 1024),
+        }
+    }
+}
+
+/// An input file processed by [`Import::run()`]
+#[derive(Clone, Copy, Debug)]
+pub struct Part<'a> {
+    /// The input file
+    pub path: &'a Path,
+    /// The number of distinct ngrams found, or `None` if the saved counts were used
+    pub ngrams: Option<usize>,
+}
+
+/// The counts resulting from [`Import::run()`], from most to least frequent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ngrams {
+    /// The unigram counts
+    pub unigrams: Vec<(String, f64)>,
+    /// The bigram counts
+    pub bigrams: Vec<((String, String), f64)>,
+}
+
+impl Ngrams {
+    /// Write the counts in the formats described in [`crate::counts`]
+    pub fn write(&self, unigrams: impl Write, bigrams: impl Write) -> Result<(), ModelError> {
+        let iter = self.unigrams.iter();
+        write_unigrams(unigrams, iter.map(|(word, count)| (word.as_str(), *count)))?;
+        let iter = self.bigrams.iter();
+        write_bigrams(
+            bigrams,
+            iter.map(|((first, second), count)| ((first.as_str(), second.as_str()), *count)),
+        )
+    }
+
+    /// Create a `Segmenter` from the counts
+    pub fn into_segmenter(self) -> Segmenter {
+        Segmenter::new(self.unigrams, self.bigrams)
+    }
+}
+
+/// Read a word list with one word per line, skipping words with characters other than letters
+///
+/// The [SCOWL][scowl] word lists used for the data files shipped with this crate work well.
+///
+/// [scowl]: http://wordlist.aspell.net/
+pub fn read_word_list(reader: impl BufRead) -> Result<HashSet<String>, ModelError> {
+    let mut words = HashSet::new();
+    for_each_line(reader, |ln| {
+        let word = ln.trim();
+        if word.bytes().all(|b| b.is_ascii_alphabetic()) {
+            words.insert(word.into());
+        }
+        Ok(())
+    })?;
+    Ok(words)
+}
+
+/// Find the `n`-gram files for `language` in `input`, preferring uncompressed files
+fn find_parts(input: &Path, language: &str, n: usize) -> Result<Vec<PathBuf>, ModelError> {
+    let prefix = format!("{language}-{n}-");
+    let mut parts = BTreeMap::new();
+    for entry in fs::read_dir(input)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !name.starts_with(&prefix) {
+            continue;
+        }
+
+        let stem = path.file_stem().unwrap_or_default().to_owned();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt") => {
+                parts.insert(stem, path);
+            }
+            Some("gz" | "zst") => {
+                parts.entry(stem).or_insert(path);
+            }
+            _ => {}
+        }
+    }
+
+    match parts.is_empty() {
+        true => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no {n}-gram files for {language} in {}", input.display()),
+        )
+        .into()),
+        false => Ok(parts.into_values().collect()),
+    }
+}
+
+fn for_each_part(
+    parts: &[PathBuf],
+    f: impl Fn(&Path) -> Result<(), ModelError> + Sync,
+) -> Result<(), ModelError> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+        parts.par_iter().try_for_each(|path| f(path))
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    parts.iter().try_for_each(|path| f(path))
+}
+
+/// The `max` most frequent `counts`, from most to least frequent
+fn most_frequent(
+    counts: HashMap<String, u64>,
+    max: Option<usize>,
+) -> impl Iterator<Item = (String, u64)> {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.into_iter().take(max.unwrap_or(usize::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join(format!("instant-segment-{}", std::process::id()));
+        let (input, state) = (dir.join("input"), dir.join("state"));
+        fs::create_dir_all(&input).unwrap();
+
+        let unigrams = "Choose\t1999,5,1\t2000,7,1\t2001,3,1\n\
+                        Spain\t2001,4,2\n\
+                        spain\t2001,1,1\n\
+                        pain_NOUN\t2001,9,1\n\
+                        xyzzy\t2001,6,1\n";
+        fs::write(input.join("eng-1-00000-00001.txt"), unigrams).unwrap();
+        let bigrams = "Choose Spain\t2005,2,1\nchoose xyzzy\t2005,8,1\n";
+        fs::write(input.join("eng-2-00000-00001.txt"), bigrams).unwrap();
+
+        let word_list = read_word_list(&b"Choose\nSpain\nspain\nit's\n"[..]).unwrap();
+        assert_eq!(word_list.len(), 3);
+        let import = Import {
+            word_list: Some(word_list),
+            max_bigrams: Some(1),
+            ..Import::default()
+        };
+
+        let processed = std::sync::Mutex::new(Vec::new());
+        let progress = |part: &Part<'_>| processed.lock().unwrap().push(part.ngrams);
+        let ngrams = import.run(&input, &state, progress).unwrap();
+        assert_eq!(
+            ngrams.unigrams,
+            [("choose".into(), 10.0), ("spain".into(), 5.0)]
+        );
+        assert_eq!(ngrams.bigrams, [(("choose".into(), "spain".into()), 2.0)]);
+        assert_eq!(*processed.lock().unwrap(), [Some(2), Some(1)]);
+
+        // The second run uses the saved counts
+        processed.lock().unwrap().clear();
+        let again = import.run(&input, &state, progress).unwrap();
+        assert_eq!(again, ngrams);
+        assert_eq!(*processed.lock().unwrap(), [None, None]);
+
+        let mut buf = Vec::new();
+        ngrams.write(&mut buf, io::sink()).unwrap();
+        assert_eq!(buf, b"choose\t10\nspain\t5\n");
+
+        let other = Import {
+            start_year: 1990,
+            ..import.clone()
+        };
+        let err = other.run(&input, &state, |_| {}).err().unwrap();
+        assert!(matches!(err, ModelError::Io(_)));
+
+        // Parse errors point out the file among all the parts
+        let part = input.join("eng-1-00001-00001.txt");
+        fs::write(&part, "Choose\t2001,x,1\n").unwrap();
+        let err = import.run(&input, &state, |_| {}).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "parse error at line 1 of {}: invalid match count",
+                part.display()
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,419 @@
+//! Prune a model to a smaller vocabulary or memory budget
+//!
+//! The model only stores relative frequencies, so count thresholds are expressed as a fraction of
+//! the total count: a threshold of `1e-9` on a model built from a billion words removes words seen
+//! less than once. Bigrams can also be pruned by their contribution to the model, using the
+//! relative entropy criterion from Stolcke, "Entropy-based Pruning of Backoff Language Models"
+//! (1998).
+
+use std::fmt;
+use std::mem;
+
+use smartstring::alias::String;
+
+use crate::{HashMap, MemoryUsage, Segmenter};
+
+impl Segmenter {
+    /// Prune the model as configured by `pruning`
+    ///
+    /// Pruning is applied in order: the unigram threshold and vocabulary size first, then the
+    /// bigram thresholds, and finally the memory budget. Removed words are treated as unknown
+    /// words when segmenting; the scores of the remaining words are not changed.
+    ///
+    /// The `held_out` segmentations are scored before and after pruning (see
+    /// [`Segmenter::held_out_score()`]) to measure the impact; pass an empty slice to skip this.
+    ///
+    /// Returns an error without changing this `Segmenter` if any of the thresholds in `pruning`
+    /// is out of range.
+    pub fn prune<S: AsRef<str>>(
+        &mut self,
+        pruning: &Pruning,
+        held_out: &[Vec<S>],
+    ) -> Result<PruneReport, InvalidPruning> {
+        pruning.validate()?;
+        let (vocab_len, bigram_len) = (self.vocab_len(), self.bigram_len());
+        let memory_before = self.memory_usage();
+        let held_out_before = self.held_out_score(held_out);
+
+        let min_unigram = pruning.min_unigram_freq.log10();
+        self.scores.retain(|_, (uni, _)| *uni >= min_unigram);
+        if let Some(max) = pruning.max_vocabulary {
+            let words = self.unigrams_by_freq();
+            self.remove_unigrams(&words[..words.len().saturating_sub(max)]);
+        }
+
+        let min_bigram = pruning.min_bigram_freq.log10();
+        self.retain_bigrams(|segmenter, prev, word, bi| {
+            bi >= min_bigram && segmenter.bigram_entropy(prev, word, bi) >= pruning.min_entropy
+        });
+
+        self.shrink_to_fit();
+        if let Some(budget) = pruning.max_memory {
+            self.prune_to_budget(budget);
+        }
+
+        Ok(PruneReport {
+            unigrams_removed: vocab_len - self.vocab_len(),
+            bigrams_removed: bigram_len - self.bigram_len(),
+            memory_before,
+            memory_after: self.memory_usage(),
+            held_out_before,
+            held_out_after: self.held_out_score(held_out),
+        })
+    }
+
+    /// The average score per word of the `held_out` segmentations
+    ///
+    /// This is the base-10 logarithm of the geometric mean of the word probabilities, as computed
+    /// by [`Segmenter::score_sentence()`]; higher is better. Returns `None` if there are no words.
+    pub fn held_out_score<S: AsRef<str>>(&self, held_out: &[Vec<S>]) -> Option<f64> {
+        let (mut total, mut words) = (0.0, 0);
+        for example in held_out {
+            if let Some(score) = self.score_sentence(example.iter().map(|w| w.as_ref())) {
+                total += score;
+                words += example.len();
+            }
+        }
+
+        match words {
+            0 => None,
+            _ => Some(total / words as f64),
+        }
+    }
+
+    /// Remove the least important bigrams, then the least frequent words, until the estimated
+    /// memory usage fits in `budget`
+    fn prune_to_budget(&mut self, budget: usize) {
+        let mut bigrams = Vec::with_capacity(self.bigram_len());
+        for (word, (_, bi_scores)) in &self.scores {
+            for (prev, &bi) in bi_scores {
+                let entropy = self.bigram_entropy(prev, word, bi);
+                bigrams.push((entropy, word.clone(), prev.clone()));
+            }
+        }
+        bigrams.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let entry_size = mem::size_of::<(String, f64)>() + 1;
+        let mut pruned = 0;
+        while pruned < bigrams.len() {
+            let excess = match self.memory_usage().total().checked_sub(budget) {
+                Some(excess) if excess > 0 => excess,
+                _ => return,
+            };
+
+            let n = (excess / entry_size).clamp(1, bigrams.len() - pruned);
+            for (_, word, prev) in &bigrams[pruned..pruned + n] {
+                if let Some((_, bi_scores)) = self.scores.get_mut(word) {
+                    bi_scores.remove(prev);
+                }
+            }
+
+            pruned += n;
+            self.shrink_to_fit();
+        }
+
+        let words = self.unigrams_by_freq();
+        let entry_size = mem::size_of::<(String, (f64, HashMap<String, f64>))>() + 1;
+        let mut pruned = 0;
+        while pruned < words.len() {
+            let excess = match self.memory_usage().total().checked_sub(budget) {
+                Some(excess) if excess > 0 => excess,
+                _ => return,
+            };
+
+            let n = (excess / entry_size).clamp(1, words.len() - pruned);
+            self.remove_unigrams(&words[pruned..pruned + n]);
+            pruned += n;
+            self.shrink_to_fit();
+        }
+    }
+
+    /// Stolcke's criterion for the bigram `prev word` with score `bi`
+    ///
+    /// Estimates the relative entropy between the model with and without the bigram, if the
+    /// unigram score is used instead. Since the model uses stupid backoff, there are no backoff
+    /// weights to renormalize, and the criterion reduces to the joint probability of the bigram
+    /// times the difference between the bigram and unigram scores.
+    fn bigram_entropy(&self, prev: &str, word: &str, bi: f64) -> f64 {
+        let uni_prev = self.scores.get(prev).map_or(0.0, |&(uni, _)| uni);
+        let uni = self.scores.get(word).map_or(0.0, |&(uni, _)| uni);
+        (10f64.powf(bi) * (bi - uni_prev - uni)).abs()
+    }
+
+    /// All words, from least to most frequent
+    fn unigrams_by_freq(&self) -> Vec<String> {
+        let mut words = self
+            .scores
+            .iter()
+            .map(|(word, &(uni, _))| (uni, word))
+            .collect::<Vec<_>>();
+        words.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.cmp(a.1)));
+        words.into_iter().map(|(_, word)| word.clone()).collect()
+    }
+
+    /// Remove `words` and the bigrams in which they are the previous word
+    fn remove_unigrams(&mut self, words: &[String]) {
+        if words.is_empty() {
+            return;
+        }
+
+        for word in words {
+            self.scores.remove(word);
+        }
+        self.retain_bigrams(|_, _, _, _| true);
+    }
+
+    /// Keep the bigrams for which `keep` returns true, and whose previous word is known
+    fn retain_bigrams(&mut self, mut keep: impl FnMut(&Self, &str, &str, f64) -> bool) {
+        let mut remove = Vec::new();
+        for (word, (_, bi_scores)) in &self.scores {
+            for (prev, &bi) in bi_scores {
+                if !self.scores.contains_key(prev) || !keep(self, prev, word, bi) {
+                    remove.push((word.clone(), prev.clone()));
+                }
+            }
+        }
+
+        for (word, prev) in remove {
+            if let Some((_, bi_scores)) = self.scores.get_mut(&word) {
+                bi_scores.remove(&prev);
+            }
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.scores.shrink_to_fit();
+        for (_, bi_scores) in self.scores.values_mut() {
+            bi_scores.shrink_to_fit();
+        }
+    }
+}
+
+/// Configuration for [`Segmenter::prune()`]
+///
+/// The default configuration doesn't remove anything. The thresholds must be finite and
+/// non-negative, and the memory budget must be positive.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pruning {
+    /// Remove words with a lower relative frequency
+    pub min_unigram_freq: f64,
+    /// Keep at most this many words, removing the least frequent ones
+    pub max_vocabulary: Option<usize>,
+    /// Remove bigrams with a lower relative frequency among all bigrams
+    pub min_bigram_freq: f64,
+    /// Remove bigrams whose removal changes the model less than this, by Stolcke's criterion
+    ///
+    /// Values around `1e-9` to `1e-7` are a reasonable starting point for large models.
+    pub min_entropy: f64,
+    /// Remove bigrams, then words, until the [`Segmenter::memory_usage()`] is within this budget
+    ///
+    /// Bigrams are removed in order of increasing entropy, words in order of increasing
+    /// frequency.
+    pub max_memory: Option<usize>,
+}
+
+impl Pruning {
+    fn validate(&self) -> Result<(), InvalidPruning> {
+        let valid = |threshold: f64| threshold.is_finite() && threshold >= 0.0;
+        if !valid(self.min_unigram_freq) {
+            Err(InvalidPruning(
+                "min_unigram_freq must be finite and non-negative",
+            ))
+        } else if !valid(self.min_bigram_freq) {
+            Err(InvalidPruning(
+                "min_bigram_freq must be finite and non-negative",
+            ))
+        } else if !valid(self.min_entropy) {
+            Err(InvalidPruning(
+                "min_entropy must be finite and non-negative",
+            ))
+        } else if self.max_memory == Some(0) {
+            Err(InvalidPruning("max_memory must be positive"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Error returned for a [`Pruning`] configuration that is out of range
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidPruning(&'static str);
+
+impl std::error::Error for InvalidPruning {}
+
+impl fmt::Display for InvalidPruning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pruning configuration: {}", self.0)
+    }
+}
+
+/// The results of [`Segmenter::prune()`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PruneReport {
+    /// The number of words removed
+    pub unigrams_removed: usize,
+    /// The number of bigrams removed, including those removed along with a word
+    pub bigrams_removed: usize,
+    /// Memory usage before pruning
+    pub memory_before: MemoryUsage,
+    /// Memory usage after pruning
+    pub memory_after: MemoryUsage,
+    /// The held-out score before pruning, if held-out data was given
+    pub held_out_before: Option<f64>,
+    /// The held-out score after pruning, if held-out data was given
+    pub held_out_after: Option<f64>,
+}
+
+impl PruneReport {
+    /// The change in held-out score due to pruning, usually negative
+    pub fn held_out_change(&self) -> Option<f64> {
+        Some(self.held_out_after? - self.held_out_before?)
+    }
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "removed {} words and {} bigrams",
+            self.unigrams_removed, self.bigrams_removed
+        )?;
+        write!(
+            f,
+            "memory: {} -> {} bytes",
+            self.memory_before.total(),
+            self.memory_after.total()
+        )?;
+        if let (Some(before), Some(after)) = (self.held_out_before, self.held_out_after) {
+            write!(
+                f,
+                "\nheld-out score per word: {before:.4} -> {after:.4} ({:+.4})",
+                after - before
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+
+    /// The shared fixture, with a second bigram
+    fn segmenter() -> Segmenter {
+        tests::segmenter(
+            &[
+                ("choose", 80_000.0),
+                ("chooses", 7_000.0),
+                ("spain", 20_000.0),
+                ("pain", 90_000.0),
+            ],
+            &[("choose", "spain", 7.0), ("chooses", "pain", 3.0)],
+        )
+    }
+
+    #[test]
+    fn test_prune() {
+        let held_out = [vec!["chooses", "pain"]];
+        let mut segmenter = segmenter();
+        let report = segmenter.prune(&Pruning::default(), &held_out).unwrap();
+        assert_eq!((report.unigrams_removed, report.bigrams_removed), (0, 0));
+        assert_eq!(report.held_out_change(), Some(0.0));
+
+        let pruning = Pruning {
+            max_vocabulary: Some(3),
+            ..Pruning::default()
+        };
+        let report = segmenter.prune(&pruning, &held_out).unwrap();
+        assert_eq!((report.unigrams_removed, report.bigrams_removed), (1, 1));
+        assert!(!segmenter.contains("chooses"));
+        assert!(report.held_out_change().unwrap() < 0.0);
+        assert!(report.memory_after.total() < report.memory_before.total());
+
+        let mut segmenter = self::segmenter();
+        let pruning = Pruning {
+            min_bigram_freq: 0.5,
+            ..Pruning::default()
+        };
+        let report = segmenter.prune(&pruning, &[] as &[Vec<&str>]).unwrap();
+        assert_eq!((report.unigrams_removed, report.bigrams_removed), (0, 1));
+        assert!(segmenter.bigram_log_prob("choose", "spain").is_some());
+        assert_eq!(report.held_out_change(), None);
+    }
+
+    #[test]
+    fn test_prune_entropy() {
+        let mut segmenter = segmenter();
+        let pain = segmenter.bigram_entropy("chooses", "pain", 0.3f64.log10());
+        let spain = segmenter.bigram_entropy("choose", "spain", 0.7f64.log10());
+        assert!(pain > 0.0 && spain > 0.0);
+
+        let pruning = Pruning {
+            min_entropy: pain.min(spain) * 1.01,
+            ..Pruning::default()
+        };
+        segmenter.prune(&pruning, &[] as &[Vec<&str>]).unwrap();
+        assert_eq!(segmenter.bigram_len(), 1);
+    }
+
+    #[test]
+    fn test_prune_memory() {
+        let mut segmenter = segmenter();
+        segmenter.shrink_to_fit();
+        let pruning = Pruning {
+            max_memory: Some(segmenter.memory_usage().unigrams),
+            ..Pruning::default()
+        };
+        let report = segmenter.prune(&pruning, &[] as &[Vec<&str>]).unwrap();
+        assert_eq!(report.bigrams_removed, 2);
+        assert!(report.memory_after.total() <= pruning.max_memory.unwrap());
+    }
+
+    #[test]
+    fn test_prune_invalid() {
+        let mut segmenter = segmenter();
+        for pruning in [
+            Pruning {
+                min_unigram_freq: -1e-9,
+                ..Pruning::default()
+            },
+            Pruning {
+                min_bigram_freq: f64::NAN,
+                ..Pruning::default()
+            },
+            Pruning {
+                min_entropy: f64::INFINITY,
+                ..Pruning::default()
+            },
+            Pruning {
+                max_memory: Some(0),
+                ..Pruning::default()
+            },
+        ] {
+            assert!(segmenter.prune(&pruning, &[] as &[Vec<&str>]).is_err());
+        }
+        assert_eq!((segmenter.vocab_len(), segmenter.bigram_len()), (4, 2));
+
+        let err = segmenter.prune(
+            &Pruning {
+                min_unigram_freq: -1.0,
+                ..Pruning::default()
+            },
+            &[] as &[Vec<&str>],
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "invalid pruning configuration: min_unigram_freq must be finite and non-negative"
+        );
+
+        // Nothing is left when everything is below the threshold
+        let pruning = Pruning {
+            min_unigram_freq: 1.0,
+            ..Pruning::default()
+        };
+        let report = segmenter.prune(&pruning, &[] as &[Vec<&str>]).unwrap();
+        assert_eq!((report.unigrams_removed, report.bigrams_removed), (4, 2));
+        assert_eq!(segmenter.vocab_len(), 0);
+    }
+}
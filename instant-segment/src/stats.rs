@@ -0,0 +1,150 @@
+use std::mem;
+
+use smartstring::alias::String;
+
+use crate::{HashMap, Segmenter};
+
+impl Segmenter {
+    /// The number of words in the vocabulary
+    pub fn vocab_len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// The number of bigrams
+    ///
+    /// This iterates over the whole vocabulary, so it's linear in [`Segmenter::vocab_len()`].
+    pub fn bigram_len(&self) -> usize {
+        self.scores
+            .values()
+            .map(|(_, bi_scores)| bi_scores.len())
+            .sum()
+    }
+
+    /// The total of the unigram counts the model was built from
+    pub fn total_count(&self) -> f64 {
+        10f64.powf(self.uni_total_log10)
+    }
+
+    /// Whether `word` is in the vocabulary
+    pub fn contains(&self, word: &str) -> bool {
+        self.scores.contains_key(word)
+    }
+
+    /// Base-10 logarithm of the relative frequency of `word`
+    ///
+    /// Returns `None` if the word is not in the vocabulary.
+    pub fn unigram_log_prob(&self, word: &str) -> Option<f64> {
+        self.scores.get(word).map(|&(uni, _)| uni)
+    }
+
+    /// Base-10 logarithm of the relative frequency of the pair `prev word` among all bigrams
+    ///
+    /// Returns `None` if the pair is not in the model. Note that this is not the score used for
+    /// `word` after `prev` while segmenting, which is relative to the unigram frequency of `prev`.
+    pub fn bigram_log_prob(&self, prev: &str, word: &str) -> Option<f64> {
+        let (_, bi_scores) = self.scores.get(word)?;
+        bi_scores.get(prev).copied()
+    }
+
+    /// Iterate over the vocabulary, yielding each word with its unigram log probability
+    ///
+    /// Words are yielded in arbitrary order.
+    pub fn vocabulary(&self) -> impl Iterator<Item = (&str, f64)> + '_ {
+        self.scores
+            .iter()
+            .map(|(word, &(uni, _))| (word.as_str(), uni))
+    }
+
+    /// Estimate the heap memory used by the model
+    ///
+    /// The estimate is based on the capacity of the hash tables and the size of words that don't
+    /// fit in a `String` inline, ignoring allocator overhead.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage {
+            unigrams: table_size(&self.scores),
+            bigrams: 0,
+        };
+
+        for (word, (_, bi_scores)) in &self.scores {
+            usage.unigrams += heap_size(word);
+            usage.bigrams += table_size(bi_scores);
+            usage.bigrams += bi_scores.keys().map(heap_size).sum::<usize>();
+        }
+
+        usage
+    }
+}
+
+/// Approximate heap memory used by a [`Segmenter`], in bytes
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryUsage {
+    /// Memory used by the vocabulary and unigram scores
+    pub unigrams: usize,
+    /// Memory used by the bigram scores
+    pub bigrams: usize,
+}
+
+impl MemoryUsage {
+    /// The total memory used
+    pub fn total(&self) -> usize {
+        self.unigrams + self.bigrams
+    }
+}
+
+fn table_size<V>(map: &HashMap<String, V>) -> usize {
+    // Each bucket holds the key and value, and one control byte
+    match map.capacity() {
+        0 => 0,
+        capacity => capacity * (mem::size_of::<(String, V)>() + 1),
+    }
+}
+
+fn heap_size(s: &String) -> usize {
+    match s.is_inline() {
+        true => 0,
+        false => s.capacity(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    #[test]
+    fn test_stats() {
+        let segmenter = choose_spain();
+        assert_eq!(segmenter.vocab_len(), 4);
+        assert_eq!(segmenter.bigram_len(), 1);
+        assert!((segmenter.total_count() - 197_000.0).abs() < 1e-6);
+        assert!(segmenter.contains("spain"));
+        assert!(!segmenter.contains("spa"));
+
+        let uni = segmenter.unigram_log_prob("pain").unwrap();
+        assert!((uni - (90_000.0f64 / 197_000.0).log10()).abs() < 1e-9);
+        assert_eq!(segmenter.unigram_log_prob("spa"), None);
+        // The only bigram has all of the bigram count
+        let bi = segmenter.bigram_log_prob("choose", "spain").unwrap();
+        assert!(bi.abs() < 1e-9);
+        assert_eq!(segmenter.bigram_log_prob("spain", "choose"), None);
+
+        let mut words = segmenter
+            .vocabulary()
+            .map(|(word, _)| word)
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        assert_eq!(words, ["choose", "chooses", "pain", "spain"]);
+
+        let usage = segmenter.memory_usage();
+        assert!(usage.unigrams > 0 && usage.bigrams > 0);
+        assert_eq!(usage.total(), usage.unigrams + usage.bigrams);
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        let segmenter = Segmenter::new([], []);
+        assert!(!segmenter.contains(""));
+        assert_eq!(segmenter.unigram_log_prob("choose"), None);
+        assert_eq!(segmenter.bigram_log_prob("choose", "spain"), None);
+    }
+}
@@ -0,0 +1,300 @@
+use std::io::{self, Read};
+use std::vec::Drain;
+
+use smartstring::alias::String;
+
+use crate::{Ascii, Candidate, InvalidCharacter, Segmenter};
+
+impl Segmenter {
+    /// Segment all text read from `reader`, passing each word to `emit`
+    ///
+    /// Words are emitted as soon as they are committed (see [`StreamSegmenter`]), so memory use
+    /// does not grow with the size of the input. ASCII whitespace, such as the line breaks in a
+    /// text file, is treated as a word boundary. Any other character that is not a lowercase
+    /// ASCII letter or digit results in an error of kind [`io::ErrorKind::InvalidData`], wrapping
+    /// an [`InvalidCharacter`] with the byte offset of the character in the whole input.
+    pub fn segment_read<R: Read>(
+        &self,
+        mut reader: R,
+        mut emit: impl FnMut(&str),
+    ) -> io::Result<()> {
+        let mut stream = StreamSegmenter::new(self);
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            for (i, run) in buf[..read].split(u8::is_ascii_whitespace).enumerate() {
+                if i > 0 {
+                    stream.offset += 1;
+                    stream.boundary().for_each(|word| emit(&word));
+                }
+
+                let words = stream
+                    .push_bytes(run)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                words.for_each(|word| emit(&word));
+            }
+        }
+
+        stream.finish().iter().for_each(|word| emit(word));
+        Ok(())
+    }
+}
+
+/// Incremental segmentation of unbounded input
+///
+/// Text is pushed in chunks of any size. A word is committed as soon as every path that could
+/// still turn out to be the best segmentation agrees on it, that is, once the best paths to all
+/// positions within the word length limit of the end of the buffer pass through the end of the
+/// word. Committed words and their text are dropped from the internal buffers, so memory use
+/// stays bounded for real-world text.
+///
+/// The result is the same as that of [`Segmenter::segment()`] for the concatenated input, except
+/// that paths with scores differing only by floating point rounding may be ranked differently.
+pub struct StreamSegmenter<'a> {
+    data: &'a Segmenter,
+    // Text that has not been committed yet
+    text: Vec<u8>,
+    // Best candidate for each end position in `text`
+    //
+    // Scores are relative to the last committed position: they are rebased on every commit, so
+    // that they don't grow without bound (and lose precision) on long streams.
+    candidates: Vec<Candidate>,
+    // The last committed word, used as context for the bigram score of the next word
+    context: Option<String>,
+    committed: Vec<String>,
+    heads: Vec<usize>,
+    // Number of bytes consumed from the input, to report invalid characters by stream position
+    offset: usize,
+}
+
+impl<'a> StreamSegmenter<'a> {
+    /// Create a streaming segmenter for the given segmenter
+    pub fn new(data: &'a Segmenter) -> Self {
+        Self {
+            data,
+            text: Vec::new(),
+            candidates: Vec::new(),
+            context: None,
+            committed: Vec::new(),
+            heads: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Add `chunk` to the input, returning the words committed as a result
+    ///
+    /// The input is validated like for [`Segmenter::segment()`]. If the `chunk` contains invalid
+    /// characters, none of it is added to the input, and the position in the error is the byte
+    /// offset from the start of the stream.
+    pub fn push(&mut self, chunk: &str) -> Result<Drain<'_, String>, InvalidCharacter> {
+        self.push_bytes(chunk.as_bytes())
+    }
+
+    fn push_bytes(&mut self, chunk: &[u8]) -> Result<Drain<'_, String>, InvalidCharacter> {
+        Ascii::validate(chunk).map_err(|mut e| {
+            e.position += self.offset;
+            e
+        })?;
+
+        self.offset += chunk.len();
+        let start = self.text.len();
+        self.text.extend_from_slice(chunk);
+        for end in start + 1..=self.text.len() {
+            self.extend(end);
+        }
+
+        if let Some(end) = self.commit_point() {
+            self.commit(end);
+        }
+
+        Ok(self.committed.drain(..))
+    }
+
+    /// Force a word boundary at the current position, returning the words committed as a result
+    ///
+    /// All pending text is committed, as if the input ended here, but the last word is kept as
+    /// context for the bigram score of the next word.
+    pub fn boundary(&mut self) -> Drain<'_, String> {
+        if !self.text.is_empty() {
+            self.commit(self.text.len());
+        }
+        self.committed.drain(..)
+    }
+
+    /// Signal the end of the input, returning the remaining words
+    pub fn finish(mut self) -> Vec<String> {
+        if !self.text.is_empty() {
+            self.commit(self.text.len());
+        }
+        self.committed
+    }
+
+    /// Find the best candidate ending at `end`
+    fn extend(&mut self, end: usize) {
+        let text = Ascii(&self.text);
+        let mut best = Candidate {
+            len: 0,
+            score: f64::NEG_INFINITY,
+        };
+
+        for split in end.saturating_sub(self.data.limit)..end {
+            let (prev, prev_score) = match split {
+                0 => (self.context.as_deref(), 0.0),
+                _ => {
+                    let prefix = self.candidates[split - 1];
+                    (Some(&text[split - prefix.len..split]), prefix.score)
+                }
+            };
+
+            let score = self.data.score(&text[split..end], prev) + prev_score;
+            if score > best.score {
+                best = Candidate {
+                    len: end - split,
+                    score,
+                };
+            }
+        }
+
+        self.candidates.push(best);
+    }
+
+    /// The last position that all paths that might still be extended pass through
+    ///
+    /// Returns `None` if that is the last committed position.
+    fn commit_point(&mut self) -> Option<usize> {
+        let len = self.text.len();
+        self.heads.clear();
+        self.heads
+            .extend((len + 1).saturating_sub(self.data.limit)..=len);
+
+        // Walk the paths back from the latest position until they all meet
+        while self.heads.len() > 1 {
+            let head = self.heads.pop().unwrap();
+            let prev = head - self.candidates[head - 1].len;
+            if let Err(idx) = self.heads.binary_search(&prev) {
+                self.heads.insert(idx, prev);
+            }
+        }
+
+        match self.heads[0] {
+            0 => None,
+            end => Some(end),
+        }
+    }
+
+    /// Commit the words on the best path to `end`, and drop them from the buffers
+    fn commit(&mut self, end: usize) {
+        let text = Ascii(&self.text);
+        let start = self.committed.len();
+        let mut pos = end;
+        while pos > 0 {
+            let len = self.candidates[pos - 1].len;
+            self.committed.push(text[pos - len..pos].into());
+            pos -= len;
+        }
+
+        self.committed[start..].reverse();
+        self.context = self.committed.last().cloned();
+
+        let base = self.candidates[end - 1].score;
+        self.text.drain(..end);
+        self.candidates.drain(..end);
+        for candidate in &mut self.candidates {
+            candidate.score -= base;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{choose_spain, segment};
+
+    fn segmenter() -> Segmenter {
+        let mut segmenter = choose_spain();
+        segmenter.add_unigrams([("now".into(), 50_000.0)]);
+        segmenter
+    }
+
+    #[test]
+    fn test_stream() {
+        let segmenter = segmenter();
+        let input = "choosespainnowchoosespainnowchoosespain";
+        let expected = segment(&segmenter, input);
+
+        let mut stream = StreamSegmenter::new(&segmenter);
+        let mut words = Vec::new();
+        for chunk in ["choo", "sespainn", "", "owchoosespainnowch", "oosespain"] {
+            words.extend(stream.push(chunk).unwrap());
+        }
+        assert!(!words.is_empty());
+        let err = stream.push("Spain").unwrap_err();
+        assert_eq!(err.position(), input.len());
+        words.extend(stream.finish());
+        assert_eq!(words, expected);
+
+        assert!(StreamSegmenter::new(&segmenter).finish().is_empty());
+    }
+
+    #[test]
+    fn test_rebase() {
+        let segmenter = segmenter();
+        let mut stream = StreamSegmenter::new(&segmenter);
+        for _ in 0..1_000 {
+            stream.push("choosespainnow").unwrap();
+            // Only the words since the last commit count towards the score
+            assert!(stream.candidates.iter().all(|c| c.score > -10.0));
+        }
+    }
+
+    #[test]
+    fn test_chunk_sizes() {
+        let segmenter = segmenter();
+        // Unknown text has many segmentations with the same score, which must be resolved the
+        // same way no matter where the chunks end
+        let unknown = format!("choose{}now", "qzx".repeat(15));
+        for input in ["choosespainnowchoosespainnowchoosespain", &unknown] {
+            let expected = segment(&segmenter, input);
+            for size in 1..=input.len() {
+                let mut stream = StreamSegmenter::new(&segmenter);
+                let mut words = Vec::new();
+                for chunk in input.as_bytes().chunks(size) {
+                    let chunk = std::str::from_utf8(chunk).unwrap();
+                    words.extend(stream.push(chunk).unwrap());
+                }
+                words.extend(stream.finish());
+                assert_eq!(words, expected, "chunk size {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_segment_read() {
+        let segmenter = segmenter();
+        let input = "choosespainnowchoosespainnowchoosespain";
+        let mut words = Vec::new();
+        segmenter
+            .segment_read(input.as_bytes(), |word| words.push(word.to_owned()))
+            .unwrap();
+        assert_eq!(words, segment(&segmenter, input));
+
+        let text = "choosespain\nnow choose\n\npain\n";
+        let mut words = Vec::new();
+        segmenter
+            .segment_read(text.as_bytes(), |word| words.push(word.to_owned()))
+            .unwrap();
+        assert_eq!(words, ["choose", "spain", "now", "choose", "pain"]);
+
+        let err = segmenter
+            .segment_read("now\nchoose Spain".as_bytes(), |_| {})
+            .unwrap_err();
+        let err = err.into_inner().unwrap().downcast::<InvalidCharacter>();
+        assert_eq!(err.unwrap().position(), 11);
+    }
+}
@@ -1,5 +1,20 @@
 use crate::{Search, Segmenter};
 
+/// Small model in which "choosespain" splits into "choose spain" or "chooses pain"
+pub fn choose_spain() -> Segmenter {
+    Segmenter::new(
+        [
+            ("choose", 80_000.0),
+            ("chooses", 7_000.0),
+            ("spain", 20_000.0),
+            ("pain", 90_000.0),
+        ]
+        .iter()
+        .map(|&(word, count)| (word.into(), count)),
+        [(("choose".into(), "spain".into()), 7.0)],
+    )
+}
+
 /// Run a segmenter against the built-in test cases
 pub fn run(segmenter: &Segmenter) {
     let mut search = Search::default();
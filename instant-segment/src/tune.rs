@@ -0,0 +1,164 @@
+//! Tune the word length limit and scoring parameters against a gold standard
+
+use crate::eval::Report;
+use crate::{InvalidParams, Params, Segmenter};
+
+impl Segmenter {
+    /// Find the best word length limit and scoring parameters in `grid` for the `gold` data
+    ///
+    /// Every combination of values in the grid is evaluated. Configurations are ranked by word
+    /// F1 score, then by exact-match accuracy; ties are resolved in favor of the earlier
+    /// configuration in the grid. The best configuration is applied to this `Segmenter`, so it
+    /// is stored when saving the model with [`Segmenter::save()`].
+    ///
+    /// Returns the evaluated configurations, ordered from best to worst, or an error without
+    /// changing this `Segmenter` if the grid contains limits or parameters that are out of range.
+    pub fn tune<S: AsRef<str>>(
+        &mut self,
+        gold: &[Vec<S>],
+        grid: &Grid,
+    ) -> Result<Vec<Trial>, InvalidParams> {
+        let mut configs = Vec::new();
+        for &limit in &grid.limits {
+            if limit == 0 {
+                return Err(InvalidParams("limit must be positive"));
+            }
+
+            for &unknown_base in &grid.unknown_base {
+                for &unknown_word_len in &grid.unknown_word_len {
+                    for &bigram_weight in &grid.bigram_weight {
+                        let params = Params {
+                            unknown_base,
+                            unknown_word_len,
+                            bigram_weight,
+                        };
+
+                        params.validate()?;
+                        configs.push((limit, params));
+                    }
+                }
+            }
+        }
+
+        let mut trials = Vec::new();
+        for (limit, params) in configs {
+            self.limit = limit;
+            self.params = params;
+            let report = self.evaluate(gold);
+            trials.push(Trial {
+                limit,
+                params,
+                report,
+            });
+        }
+
+        // Stable sort, so earlier configurations win ties
+        trials.sort_by(|a, b| {
+            let key = |trial: &Trial| (trial.report.words.f1(), trial.report.accuracy());
+            let (a, b) = (key(a), key(b));
+            b.0.total_cmp(&a.0).then(b.1.total_cmp(&a.1))
+        });
+
+        if let Some(best) = trials.first() {
+            self.limit = best.limit;
+            self.params = best.params;
+        }
+
+        Ok(trials)
+    }
+}
+
+/// Values to try for each parameter in [`Segmenter::tune()`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    /// Word length limits, which must be positive
+    pub limits: Vec<usize>,
+    /// Values for [`Params::unknown_base`]
+    pub unknown_base: Vec<f64>,
+    /// Values for [`Params::unknown_word_len`]
+    pub unknown_word_len: Vec<f64>,
+    /// Values for [`Params::bigram_weight`]
+    pub bigram_weight: Vec<f64>,
+}
+
+impl Default for Grid {
+    /// A grid around the default parameters, with 108 configurations
+    fn default() -> Self {
+        Self {
+            limits: vec![16, 20, 24, 28],
+            unknown_base: vec![0.0, 1.0, 2.0],
+            unknown_word_len: vec![4.0, 5.0, 6.0],
+            bigram_weight: vec![0.6, 0.8, 1.0],
+        }
+    }
+}
+
+/// An evaluated configuration from [`Segmenter::tune()`]
+#[derive(Clone, Debug)]
+pub struct Trial {
+    /// The word length limit
+    pub limit: usize,
+    /// The scoring parameters
+    pub params: Params,
+    /// The results of evaluating this configuration
+    pub report: Report,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::choose_spain;
+
+    fn grid() -> Grid {
+        Grid {
+            limits: vec![4, 24],
+            unknown_base: vec![1.0],
+            unknown_word_len: vec![5.0],
+            bigram_weight: vec![1.0, 0.5],
+        }
+    }
+
+    #[test]
+    fn test_tune() {
+        let mut segmenter = choose_spain();
+        let gold = [vec!["choose", "spain"], vec!["choose", "xyzzy"]];
+        let trials = segmenter.tune(&gold, &grid()).unwrap();
+        assert_eq!(trials.len(), 4);
+        assert_eq!(trials[0].limit, 24);
+        assert_eq!(trials[0].params.bigram_weight, 1.0);
+        assert_eq!(trials[3].limit, 4);
+        assert!(trials[0].report.words.f1() > trials[3].report.words.f1());
+        assert_eq!(segmenter.limit(), 24);
+        assert_eq!(segmenter.params(), &trials[0].params);
+    }
+
+    #[test]
+    fn test_tune_invalid() {
+        let mut segmenter = choose_spain();
+        segmenter.set_limit(12);
+        let gold = [vec!["choose", "spain"]];
+
+        // Invalid values late in the grid are found before any configuration is applied
+        for grid in [
+            Grid {
+                limits: vec![24, 0],
+                ..grid()
+            },
+            Grid {
+                unknown_word_len: vec![5.0, 0.0],
+                ..grid()
+            },
+        ] {
+            assert!(segmenter.tune(&gold, &grid).is_err());
+            assert_eq!(segmenter.limit(), 12);
+            assert_eq!(segmenter.params(), &Params::default());
+        }
+
+        let empty = Grid {
+            limits: Vec::new(),
+            ..grid()
+        };
+        assert!(segmenter.tune(&gold, &empty).unwrap().is_empty());
+        assert_eq!(segmenter.limit(), 12);
+    }
+}
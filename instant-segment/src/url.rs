@@ -0,0 +1,200 @@
+//! Segmentation of URLs
+//!
+//! Host labels, path segments, query parameters and fragments are percent-decoded and
+//! segmented separately, such that separators in the URL always end up as word boundaries.
+
+use std::borrow::Cow;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use smartstring::alias::String;
+
+use crate::{Search, Segmenter};
+
+impl Segmenter {
+    /// Segment the components of the given `url`
+    ///
+    /// Parsing is lenient: the scheme, user info and port are optional and ignored, and inputs
+    /// starting with a `/` are treated as a path without a host. Each component is
+    /// percent-decoded and then segmented like an identifier (see
+    /// [`Segmenter::segment_identifier()`]), so separators like `-`, `_`, `.` and `+` as well as
+    /// case transitions are respected.
+    pub fn segment_url(&self, url: &str, search: &mut Search) -> SegmentedUrl {
+        let (rest, fragment) = match url.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (url, None),
+        };
+
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+
+        let rest = match rest.find("://") {
+            Some(idx) if !rest[..idx].contains('/') => &rest[idx + 3..],
+            _ => rest,
+        };
+
+        let (authority, path) = match rest.starts_with('/') {
+            true => ("", rest),
+            false => match rest.find('/') {
+                Some(idx) => rest.split_at(idx),
+                None => (rest, ""),
+            },
+        };
+
+        let host = match authority.rsplit_once('@') {
+            Some((_, host)) => host,
+            None => authority,
+        };
+
+        let host = match host.rfind(':') {
+            Some(idx) if host[idx + 1..].bytes().all(|b| b.is_ascii_digit()) => &host[..idx],
+            _ => host,
+        };
+
+        let mut component = |text: &str| {
+            let text = percent_decode(text);
+            UrlComponent {
+                words: self.segment_identifier(&text, search),
+                text: text.as_ref().into(),
+            }
+        };
+
+        SegmentedUrl {
+            host: host
+                .split('.')
+                .filter(|label| !label.is_empty())
+                .map(&mut component)
+                .collect(),
+            path: path
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(&mut component)
+                .collect(),
+            query: query
+                .unwrap_or_default()
+                .split('&')
+                .filter(|param| !param.is_empty())
+                .map(|param| match param.split_once('=') {
+                    Some((key, value)) => (component(key), Some(component(value))),
+                    None => (component(param), None),
+                })
+                .collect(),
+            fragment: fragment
+                .filter(|fragment| !fragment.is_empty())
+                .map(component),
+        }
+    }
+}
+
+/// Result of [`Segmenter::segment_url()`]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct SegmentedUrl {
+    /// Labels of the host name, in order of appearance
+    pub host: Vec<UrlComponent>,
+    /// Non-empty path segments
+    pub path: Vec<UrlComponent>,
+    /// Query parameters, with the value if the parameter has one
+    pub query: Vec<(UrlComponent, Option<UrlComponent>)>,
+    /// The fragment, if any
+    pub fragment: Option<UrlComponent>,
+}
+
+/// A single segmented component of a URL
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(Serialize))]
+pub struct UrlComponent {
+    /// The percent-decoded text of the component
+    pub text: String,
+    /// The words found in the component
+    pub words: Vec<String>,
+}
+
+/// Decode `%XX` escapes in `input`, leaving invalid escapes as they are
+fn percent_decode(input: &str) -> Cow<'_, str> {
+    if !input.contains('%') {
+        return Cow::Borrowed(input);
+    }
+
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match (bytes[i], bytes.get(i + 1..i + 3)) {
+            (b'%', Some(&[hi, lo])) => hex(hi).zip(hex(lo)),
+            _ => None,
+        };
+
+        match escaped {
+            Some((hi, lo)) => {
+                decoded.push(hi << 4 | lo);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    Cow::Owned(std::string::String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn hex(b: u8) -> Option<u8> {
+    char::from(b).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("how%20to"), "how to");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%41"), "%zzA");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+    }
+
+    #[test]
+    fn test_segment_url() {
+        let segmenter = crate::tests::segmenter(
+            &[
+                ("how", 10_000.0),
+                ("to", 50_000.0),
+                ("build", 5_000.0),
+                ("a", 50_000.0),
+                ("rust", 1_000.0),
+                ("crate", 1_000.0),
+                ("blog", 1_000.0),
+                ("my", 20_000.0),
+                ("com", 1_000.0),
+            ],
+            &[],
+        );
+
+        let mut search = Search::default();
+        let url = segmenter.segment_url(
+            "https://user@www.myblog.com:8080/blog/howtobuildarustcrate?q=rust%20crate#howto",
+            &mut search,
+        );
+
+        assert_eq!(url.host.len(), 3);
+        assert_eq!(url.host[1].words, ["my", "blog"]);
+        assert_eq!(url.path[0].text, "blog");
+        assert_eq!(
+            url.path[1].words,
+            ["how", "to", "build", "a", "rust", "crate"]
+        );
+        let (key, value) = &url.query[0];
+        assert_eq!(key.text, "q");
+        assert_eq!(value.as_ref().unwrap().words, ["rust", "crate"]);
+        assert_eq!(url.fragment.as_ref().unwrap().words, ["how", "to"]);
+
+        let url = segmenter.segment_url("/blog/how-to", &mut search);
+        assert!(url.host.is_empty());
+        assert_eq!(url.path[1].words, ["how", "to"]);
+    }
+}